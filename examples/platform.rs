@@ -1,22 +1,26 @@
-use monolith::platform::unix::filesystem::{Filesystem, INode};
+use monolith::arena::Arena;
+use monolith::platform::unix::filesystem::{Filesystem, ScanOptions};
+use monolith::platform::unix::glob::Glob;
 use std::ops::Deref;
 
-fn print_entries(root_node: &Vec<INode>) {
-    for entry in root_node {
-        match entry {
-            INode::File(string) => {
-                println!("{}", string.deref());
-            }
-            INode::Directory(nodes) => {
-                print_entries(&nodes);
-            }
-        }
-    }
-}
-
 fn main() {
-    let filesystem = Filesystem::new(".");
-    let root = filesystem.read();
+    let filesystem = Filesystem::new(".").unwrap();
+    let options = ScanOptions {
+        ignore_files: true,
+        ..Default::default()
+    };
+    let root = filesystem.read(&options).unwrap();
+    let arena = Arena::new(4096);
+
+    for node in root.depth_first().filter(|node| node.is_file()) {
+        let scratch = arena.scratch();
+        let path = filesystem.paths().render(&scratch, node.path).unwrap();
+        println!("{} ({} bytes)", path.deref(), node.size);
+    }
 
-    print_entries(&root);
+    let sources = Glob::new("**/*.rs").unwrap();
+    println!(
+        "{} rust sources",
+        root.glob(filesystem.paths(), &sources).count()
+    );
 }
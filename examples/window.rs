@@ -104,6 +104,7 @@ fn main() {
                 }
 
                 clock.update();
+                env.frames().swap();
             }
             _ => (),
         }
@@ -0,0 +1,260 @@
+use super::atomic::bump_atomic;
+use super::Arena;
+use allocator_api2::alloc::{AllocError, Allocator, Layout};
+use core::cell::UnsafeCell;
+use core::ptr::NonNull;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::alloc::{GlobalAlloc, System};
+
+// Serves allocator-aware collections from an arena. It is made from a mutable
+// borrow, so the arena can't be cleared, reset or dropped while a collection
+// still holds memory from it.
+#[derive(Clone, Copy)]
+pub struct ArenaAllocator<'a> {
+    arena: &'a Arena,
+}
+
+impl Arena {
+    pub fn allocator(&mut self) -> ArenaAllocator<'_> {
+        ArenaAllocator { arena: self }
+    }
+}
+
+unsafe impl Allocator for ArenaAllocator<'_> {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let ptr = self
+            .arena
+            .reserve(layout.size(), layout.align())
+            .ok()
+            .and_then(NonNull::new)
+            .ok_or(AllocError)?;
+
+        Ok(NonNull::slice_from_raw_parts(ptr, layout.size()))
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        self.arena.release_last(ptr.as_ptr(), layout.size());
+    }
+
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        let aligned = ptr.as_ptr() as usize & (new_layout.align() - 1) == 0;
+
+        if aligned
+            && self
+                .arena
+                .grow_in_place(ptr.as_ptr(), old_layout.size(), new_layout.size())
+        {
+            return Ok(NonNull::slice_from_raw_parts(ptr, new_layout.size()));
+        }
+
+        let new_ptr = Allocator::allocate(self, new_layout)?;
+        new_ptr
+            .as_ptr()
+            .cast::<u8>()
+            .copy_from_nonoverlapping(ptr.as_ptr(), old_layout.size());
+
+        Ok(new_ptr)
+    }
+
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        if ptr.as_ptr() as usize & (new_layout.align() - 1) != 0 {
+            let new_ptr = Allocator::allocate(self, new_layout)?;
+            new_ptr
+                .as_ptr()
+                .cast::<u8>()
+                .copy_from_nonoverlapping(ptr.as_ptr(), new_layout.size());
+
+            return Ok(new_ptr);
+        }
+
+        self.arena
+            .grow_in_place(ptr.as_ptr(), old_layout.size(), new_layout.size());
+        Ok(NonNull::slice_from_raw_parts(ptr, new_layout.size()))
+    }
+}
+
+// A global allocator that serves allocations from a fixed static region while a
+// frame is active and falls back to the system allocator otherwise. Memory from
+// the region is only reclaimed all at once by `end_frame`.
+pub struct BumpAllocator<const N: usize> {
+    region: UnsafeCell<[u8; N]>,
+    offset: AtomicUsize,
+    active: AtomicBool,
+}
+
+unsafe impl<const N: usize> Sync for BumpAllocator<N> {}
+
+impl<const N: usize> BumpAllocator<N> {
+    pub const fn new() -> Self {
+        BumpAllocator {
+            region: UnsafeCell::new([0; N]),
+            offset: AtomicUsize::new(0),
+            active: AtomicBool::new(false),
+        }
+    }
+
+    pub fn begin_frame(&self) {
+        self.active.store(true, Ordering::Release);
+    }
+
+    /// # Safety
+    ///
+    /// Every allocation made since `begin_frame` must have been freed, or never
+    /// be used again, before the region is handed out a second time.
+    pub unsafe fn end_frame(&self) {
+        self.active.store(false, Ordering::Release);
+        self.offset.store(0, Ordering::Release);
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active.load(Ordering::Acquire)
+    }
+
+    pub fn occupied(&self) -> usize {
+        self.offset.load(Ordering::Acquire)
+    }
+
+    pub fn size(&self) -> usize {
+        N
+    }
+
+    pub fn contains(&self, ptr: *const u8) -> bool {
+        let base = self.region.get() as usize;
+        (base..base + N).contains(&(ptr as usize))
+    }
+
+    fn bump(&self, layout: Layout) -> Option<*mut u8> {
+        let base = self.region.get() as usize;
+        bump_atomic(base, N, &self.offset, layout.size(), layout.align())
+    }
+}
+
+impl<const N: usize> Default for BumpAllocator<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+unsafe impl<const N: usize> GlobalAlloc for BumpAllocator<N> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if self.is_active() {
+            if let Some(ptr) = self.bump(layout) {
+                return ptr;
+            }
+        }
+
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if !self.contains(ptr) {
+            System.dealloc(ptr, layout);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use allocator_api2::boxed::Box;
+    use allocator_api2::vec::Vec;
+
+    #[test]
+    fn test_arena_allocator_vec() {
+        let mut arena = Arena::new(1024);
+        let mut values = Vec::new_in(arena.allocator());
+
+        for i in 0..64u32 {
+            values.push(i);
+        }
+
+        assert_eq!(values.len(), 64);
+        assert_eq!(values[63], 63);
+
+        drop(values);
+        assert_eq!(arena.occupied(), 0);
+    }
+
+    #[test]
+    fn test_arena_allocator_grows_in_place() {
+        let mut arena = Arena::new(1024);
+        let mut values: Vec<u64, _> = Vec::with_capacity_in(4, arena.allocator());
+        values.extend_from_slice(&[1, 2, 3, 4]);
+        let ptr = values.as_ptr();
+
+        values.extend_from_slice(&[5, 6, 7, 8]);
+        assert_eq!(values.as_ptr(), ptr);
+
+        // A relocated vector would have left its first buffer behind.
+        drop(values);
+        assert_eq!(arena.occupied(), 0);
+    }
+
+    #[test]
+    fn test_arena_allocator_box() {
+        let mut arena = Arena::growable(64);
+
+        {
+            let allocator = arena.allocator();
+            let boxed = Box::new_in([7u8; 100], allocator);
+            let aligned = Box::new_in(Aligned([1; 4]), allocator);
+
+            assert_eq!(boxed[99], 7);
+            assert_eq!(aligned.0[3], 1);
+            assert_eq!(&*aligned as *const Aligned as usize % 64, 0);
+        }
+
+        assert_eq!(arena.blocks(), 3);
+    }
+
+    #[repr(align(64))]
+    struct Aligned([u32; 4]);
+
+    #[test]
+    fn test_arena_allocator_full() {
+        let mut arena = Arena::new(16);
+        let mut values: Vec<u64, _> = Vec::new_in(arena.allocator());
+
+        assert!(values.try_reserve(32).is_err());
+    }
+
+    #[test]
+    fn test_bump_allocator() {
+        static BUMP: BumpAllocator<1024> = BumpAllocator::new();
+        let layout = Layout::from_size_align(64, 16).unwrap();
+
+        unsafe {
+            let outside = BUMP.alloc(layout);
+            assert!(!BUMP.contains(outside));
+
+            BUMP.begin_frame();
+            let first = BUMP.alloc(layout);
+            let second = BUMP.alloc(layout);
+            let large = BUMP.alloc(Layout::from_size_align(4096, 8).unwrap());
+
+            assert!(BUMP.contains(first));
+            assert!(BUMP.contains(second));
+            assert!(!BUMP.contains(large));
+            assert_eq!(first as usize % 16, 0);
+            assert_eq!(second as usize - first as usize, 64);
+
+            BUMP.dealloc(first, layout);
+            BUMP.dealloc(second, layout);
+            BUMP.dealloc(large, Layout::from_size_align(4096, 8).unwrap());
+            BUMP.end_frame();
+
+            assert_eq!(BUMP.occupied(), 0);
+            BUMP.dealloc(outside, layout);
+        }
+    }
+}
@@ -0,0 +1,354 @@
+use super::AllocError;
+use alloc::alloc::{alloc_zeroed, dealloc, Layout};
+use core::cell::Cell;
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use core::ptr::NonNull;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+const ALIGN: usize = 16;
+
+pub struct AtomicArena {
+    data: NonNull<u8>,
+    size: usize,
+    offset: AtomicUsize,
+}
+
+pub struct LocalArena<'a> {
+    data: NonNull<u8>,
+    size: usize,
+    offset: Cell<usize>,
+    _shared: PhantomData<&'a AtomicArena>,
+}
+
+unsafe impl Send for AtomicArena {}
+unsafe impl Sync for AtomicArena {}
+unsafe impl Send for LocalArena<'_> {}
+
+pub(super) fn bump_atomic(
+    base: usize,
+    size: usize,
+    offset: &AtomicUsize,
+    len: usize,
+    align: usize,
+) -> Option<*mut u8> {
+    let mut current = offset.load(Ordering::Relaxed);
+
+    loop {
+        let start = (base + current).checked_add(align - 1)? & !(align - 1);
+        let end = start.checked_add(len)?;
+
+        if end > base + size {
+            return None;
+        }
+
+        match offset.compare_exchange_weak(current, end - base, Ordering::AcqRel, Ordering::Relaxed)
+        {
+            Ok(_) => return Some(start as *mut u8),
+            Err(actual) => current = actual,
+        }
+    }
+}
+
+fn bump_local(
+    base: usize,
+    size: usize,
+    offset: &Cell<usize>,
+    len: usize,
+    align: usize,
+) -> Option<*mut u8> {
+    let start = (base + offset.get()).checked_add(align - 1)? & !(align - 1);
+    let end = start.checked_add(len)?;
+
+    if end > base + size {
+        return None;
+    }
+
+    offset.set(end - base);
+    Some(start as *mut u8)
+}
+
+unsafe fn write_slice<'a, T: Clone>(ptr: *mut u8, values: &[T]) -> &'a mut [T] {
+    let ptr = ptr as *mut T;
+
+    for (i, value) in values.iter().enumerate() {
+        ptr.add(i).write(value.clone());
+    }
+
+    core::slice::from_raw_parts_mut(ptr, values.len())
+}
+
+// Allocations never overlap, so handing out `&mut` from `&self` is sound here.
+#[allow(clippy::mut_from_ref)]
+impl AtomicArena {
+    pub fn new(size: usize) -> AtomicArena {
+        let data = if size == 0 {
+            NonNull::<u128>::dangling().cast()
+        } else {
+            let layout = Layout::from_size_align(size, ALIGN).unwrap();
+            NonNull::new(unsafe { alloc_zeroed(layout) }).unwrap()
+        };
+
+        AtomicArena {
+            data,
+            size,
+            offset: AtomicUsize::new(0),
+        }
+    }
+
+    fn reserve(&self, len: usize, align: usize) -> Result<*mut u8, AllocError> {
+        if len == 0 {
+            return Ok(align as *mut u8);
+        }
+
+        bump_atomic(
+            self.data.as_ptr() as usize,
+            self.size,
+            &self.offset,
+            len,
+            align,
+        )
+        .ok_or(AllocError {
+            requested: len,
+            align,
+            remaining: self.size - self.occupied(),
+        })
+    }
+
+    // The memory may still hold an earlier frame's values, so it is handed out
+    // uninitialized for the caller to write.
+    pub fn allocate<T>(&self, len: usize) -> Result<&mut [MaybeUninit<T>], AllocError> {
+        let size = core::mem::size_of::<T>()
+            .checked_mul(len)
+            .ok_or(AllocError {
+                requested: usize::MAX,
+                align: core::mem::align_of::<T>(),
+                remaining: self.size - self.occupied(),
+            })?;
+        let ptr = self.reserve(size, core::mem::align_of::<T>())? as *mut MaybeUninit<T>;
+
+        Ok(unsafe { core::slice::from_raw_parts_mut(ptr, len) })
+    }
+
+    pub fn push<T>(&self, value: T) -> Result<&mut T, AllocError> {
+        let ptr = self.reserve(core::mem::size_of::<T>(), core::mem::align_of::<T>())? as *mut T;
+
+        unsafe {
+            ptr.write(value);
+            Ok(&mut *ptr)
+        }
+    }
+
+    pub fn push_slice<T: Clone>(&self, values: &[T]) -> Result<&mut [T], AllocError> {
+        let ptr = self.reserve(core::mem::size_of_val(values), core::mem::align_of::<T>())?;
+
+        Ok(unsafe { write_slice(ptr, values) })
+    }
+
+    pub fn push_str(&self, string: &str) -> Result<&mut str, AllocError> {
+        let bytes = self.push_slice(string.as_bytes())?;
+
+        Ok(unsafe { core::str::from_utf8_unchecked_mut(bytes) })
+    }
+
+    pub fn local(&self, size: usize) -> Result<LocalArena<'_>, AllocError> {
+        let ptr = self.reserve(size, ALIGN)?;
+
+        Ok(LocalArena {
+            data: unsafe { NonNull::new_unchecked(ptr) },
+            size,
+            offset: Cell::new(0),
+            _shared: PhantomData,
+        })
+    }
+
+    // Taking `&mut self` guarantees that no worker still holds an allocation or
+    // a local arena when the frame's memory is handed out again.
+    pub fn clear(&mut self) {
+        *self.offset.get_mut() = 0;
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn occupied(&self) -> usize {
+        self.offset.load(Ordering::Acquire)
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.occupied() == self.size
+    }
+}
+
+impl Drop for AtomicArena {
+    fn drop(&mut self) {
+        if self.size > 0 {
+            let layout = Layout::from_size_align(self.size, ALIGN).unwrap();
+            unsafe { dealloc(self.data.as_ptr(), layout) };
+        }
+    }
+}
+
+#[allow(clippy::mut_from_ref)]
+impl LocalArena<'_> {
+    fn reserve(&self, len: usize, align: usize) -> Result<*mut u8, AllocError> {
+        if len == 0 {
+            return Ok(align as *mut u8);
+        }
+
+        bump_local(
+            self.data.as_ptr() as usize,
+            self.size,
+            &self.offset,
+            len,
+            align,
+        )
+        .ok_or(AllocError {
+            requested: len,
+            align,
+            remaining: self.size - self.occupied(),
+        })
+    }
+
+    pub fn allocate<T>(&self, len: usize) -> Result<&mut [MaybeUninit<T>], AllocError> {
+        let size = core::mem::size_of::<T>()
+            .checked_mul(len)
+            .ok_or(AllocError {
+                requested: usize::MAX,
+                align: core::mem::align_of::<T>(),
+                remaining: self.size - self.occupied(),
+            })?;
+        let ptr = self.reserve(size, core::mem::align_of::<T>())? as *mut MaybeUninit<T>;
+
+        Ok(unsafe { core::slice::from_raw_parts_mut(ptr, len) })
+    }
+
+    pub fn push<T>(&self, value: T) -> Result<&mut T, AllocError> {
+        let ptr = self.reserve(core::mem::size_of::<T>(), core::mem::align_of::<T>())? as *mut T;
+
+        unsafe {
+            ptr.write(value);
+            Ok(&mut *ptr)
+        }
+    }
+
+    pub fn push_slice<T: Clone>(&self, values: &[T]) -> Result<&mut [T], AllocError> {
+        let ptr = self.reserve(core::mem::size_of_val(values), core::mem::align_of::<T>())?;
+
+        Ok(unsafe { write_slice(ptr, values) })
+    }
+
+    pub fn push_str(&self, string: &str) -> Result<&mut str, AllocError> {
+        let bytes = self.push_slice(string.as_bytes())?;
+
+        Ok(unsafe { core::str::from_utf8_unchecked_mut(bytes) })
+    }
+
+    pub fn clear(&mut self) {
+        self.offset.set(0);
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn occupied(&self) -> usize {
+        self.offset.get()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.occupied() == self.size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_atomic_arena() {
+        let mut arena = AtomicArena::new(1024);
+
+        let a = arena.push(1u64).unwrap();
+        let b = arena.push_slice(&[1u32, 2, 3]).unwrap();
+        let c = arena.push_str("worker").unwrap();
+        *a += 1;
+
+        assert_eq!(*a, 2);
+        assert_eq!(b, &[1, 2, 3]);
+        assert_eq!(c, "worker");
+        assert_eq!(arena.occupied(), 8 + 12 + 6);
+
+        arena.clear();
+        assert_eq!(arena.occupied(), 0);
+        assert!(arena.allocate::<u8>(1025).is_err());
+        assert!(arena.allocate::<u64>(usize::MAX / 4).is_err());
+        assert!(arena.allocate::<u8>(1024).is_ok());
+        assert!(arena.is_full());
+    }
+
+    #[test]
+    fn test_atomic_arena_threads() {
+        let mut arena = AtomicArena::new(64 * 1024);
+
+        for _frame in 0..2 {
+            let shared = &arena;
+
+            let sums: Vec<u64> = std::thread::scope(|scope| {
+                let workers: Vec<_> = (0..4u64)
+                    .map(|worker| {
+                        scope.spawn(move || {
+                            let mut sum = 0;
+
+                            for i in 0..256u64 {
+                                let value = shared.push(worker * 1000 + i).unwrap();
+                                sum += *value;
+                            }
+
+                            sum
+                        })
+                    })
+                    .collect();
+
+                workers.into_iter().map(|w| w.join().unwrap()).collect()
+            });
+
+            for (worker, sum) in sums.iter().enumerate() {
+                assert_eq!(*sum, worker as u64 * 256_000 + (0..256).sum::<u64>());
+            }
+
+            assert_eq!(arena.occupied(), 4 * 256 * 8);
+            arena.clear();
+        }
+    }
+
+    #[test]
+    fn test_local_arena() {
+        let arena = AtomicArena::new(64 * 1024);
+
+        std::thread::scope(|scope| {
+            for worker in 0..4u32 {
+                let mut local = arena.local(4096).unwrap();
+
+                scope.spawn(move || {
+                    let values = local.allocate::<u32>(512).unwrap();
+
+                    for (i, value) in values.iter_mut().enumerate() {
+                        value.write(worker + i as u32);
+                    }
+
+                    assert_eq!(unsafe { values[511].assume_init() }, worker + 511);
+                    assert_eq!(local.occupied(), 2048);
+                    assert!(local.allocate::<u32>(513).is_err());
+
+                    local.clear();
+                    assert_eq!(local.push_str("again").unwrap(), "again");
+                });
+            }
+        });
+
+        assert_eq!(arena.occupied(), 4 * 4096);
+    }
+}
@@ -0,0 +1,347 @@
+use super::{Arena, ArenaMark};
+use alloc::vec::Vec;
+use core::cell::{Cell, RefCell};
+use core::fmt;
+
+pub(super) const POISON: u8 = 0xDD;
+pub(super) const CANARY: [u8; 8] = [0xFD; 8];
+const UNTAGGED: &str = "untagged";
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DiagnosticOptions {
+    pub poison: bool,
+    pub canaries: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TagUsage {
+    pub tag: &'static str,
+    pub bytes: usize,
+    pub allocations: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArenaReport {
+    pub size: usize,
+    pub occupied: usize,
+    pub high_water: usize,
+    pub blocks: usize,
+    pub wasted: usize,
+    pub clears: usize,
+    pub tags: Vec<TagUsage>,
+}
+
+pub struct TagScope<'a> {
+    arena: &'a Arena,
+    previous: &'static str,
+}
+
+struct Guard {
+    mark: ArenaMark,
+    ptr: *const u8,
+    size: usize,
+    tag: &'static str,
+}
+
+pub(super) struct Diagnostics {
+    options: DiagnosticOptions,
+    high_water: Cell<usize>,
+    clears: Cell<usize>,
+    tag: Cell<&'static str>,
+    tags: RefCell<Vec<TagUsage>>,
+    guards: RefCell<Vec<Guard>>,
+}
+
+impl Diagnostics {
+    pub(super) fn new(options: DiagnosticOptions) -> Diagnostics {
+        Diagnostics {
+            options,
+            high_water: Cell::new(0),
+            clears: Cell::new(0),
+            tag: Cell::new(UNTAGGED),
+            tags: RefCell::new(Vec::new()),
+            guards: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub(super) fn guard_size(&self) -> usize {
+        if self.options.canaries {
+            CANARY.len()
+        } else {
+            0
+        }
+    }
+
+    pub(super) fn poison(&self) -> bool {
+        self.options.poison
+    }
+
+    pub(super) fn record(&self, mark: ArenaMark, ptr: *mut u8, size: usize, occupied: usize) {
+        let tag = self.tag.get();
+
+        if self.options.canaries {
+            unsafe {
+                ptr.add(size)
+                    .copy_from_nonoverlapping(CANARY.as_ptr(), CANARY.len());
+            }
+
+            self.guards.borrow_mut().push(Guard {
+                mark,
+                ptr: unsafe { ptr.add(size) },
+                size,
+                tag,
+            });
+        }
+
+        let mut tags = self.tags.borrow_mut();
+
+        match tags.iter_mut().find(|usage| usage.tag == tag) {
+            Some(usage) => {
+                usage.bytes += size;
+                usage.allocations += 1;
+            }
+            None => tags.push(TagUsage {
+                tag,
+                bytes: size,
+                allocations: 1,
+            }),
+        }
+
+        self.high_water.set(self.high_water.get().max(occupied));
+    }
+
+    pub(super) fn verify(&self, mark: ArenaMark) {
+        let mut guards = self.guards.borrow_mut();
+
+        while let Some(guard) = guards.last() {
+            if guard.mark < mark {
+                break;
+            }
+
+            let canary = unsafe { core::slice::from_raw_parts(guard.ptr, CANARY.len()) };
+
+            if canary != CANARY {
+                panic!(
+                    "arena canary overwritten after a {} byte allocation tagged \"{}\"",
+                    guard.size, guard.tag
+                );
+            }
+
+            guards.pop();
+        }
+    }
+
+    pub(super) fn cleared(&self) {
+        self.clears.set(self.clears.get() + 1);
+    }
+}
+
+impl Arena {
+    pub fn enable_diagnostics(&mut self, options: DiagnosticOptions) {
+        let diagnostics = Diagnostics::new(options);
+        diagnostics.high_water.set(self.occupied());
+        self.diagnostics = Some(alloc::boxed::Box::new(diagnostics));
+    }
+
+    pub fn has_diagnostics(&self) -> bool {
+        self.diagnostics.is_some()
+    }
+
+    pub fn tag(&self, tag: &'static str) -> TagScope<'_> {
+        let previous = match &self.diagnostics {
+            Some(diagnostics) => diagnostics.tag.replace(tag),
+            None => UNTAGGED,
+        };
+
+        TagScope {
+            arena: self,
+            previous,
+        }
+    }
+
+    pub fn high_water(&self) -> usize {
+        match &self.diagnostics {
+            Some(diagnostics) => diagnostics.high_water.get(),
+            None => self.occupied(),
+        }
+    }
+
+    pub fn report(&self) -> ArenaReport {
+        let (high_water, clears, tags) = match &self.diagnostics {
+            Some(diagnostics) => (
+                diagnostics.high_water.get(),
+                diagnostics.clears.get(),
+                diagnostics.tags.borrow().clone(),
+            ),
+            None => (self.occupied(), 0, Vec::new()),
+        };
+
+        ArenaReport {
+            size: self.size(),
+            occupied: self.occupied(),
+            high_water,
+            blocks: self.blocks(),
+            wasted: self.wasted(),
+            clears,
+            tags,
+        }
+    }
+}
+
+impl Drop for TagScope<'_> {
+    fn drop(&mut self) {
+        if let Some(diagnostics) = &self.arena.diagnostics {
+            diagnostics.tag.set(self.previous);
+        }
+    }
+}
+
+impl fmt::Display for ArenaReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "arena: {} bytes in {} block(s), {} occupied, {} high water, {} wasted, {} clear(s)",
+            self.size, self.blocks, self.occupied, self.high_water, self.wasted, self.clears
+        )?;
+
+        for usage in self.tags.iter() {
+            writeln!(
+                f,
+                "  {:<16} {:>10} bytes in {} allocation(s)",
+                usage.tag, usage.bytes, usage.allocations
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_high_water() {
+        let mut arena = Arena::new(1024);
+        arena.enable_diagnostics(DiagnosticOptions::default());
+
+        let _a = arena.allocate::<u8>(300).unwrap();
+        arena.clear();
+        let _b = arena.allocate::<u8>(100).unwrap();
+
+        assert_eq!(arena.occupied(), 100);
+        assert_eq!(arena.high_water(), 300);
+        assert_eq!(arena.report().clears, 1);
+    }
+
+    #[test]
+    fn test_tags() {
+        let mut arena = Arena::new(1024);
+        arena.enable_diagnostics(DiagnosticOptions::default());
+
+        let _a = arena.allocate::<u8>(10).unwrap();
+
+        {
+            let _mesh = arena.tag("mesh");
+            let _b = arena.allocate::<u32>(4).unwrap();
+            let _c = arena.allocate::<u32>(4).unwrap();
+
+            {
+                let _fs = arena.tag("fs");
+                let _d = arena.push_string("assets/wall.png").unwrap();
+            }
+
+            let _e = arena.allocate::<u8>(1).unwrap();
+        }
+
+        let report = arena.report();
+        let usage = |tag| report.tags.iter().find(|usage| usage.tag == tag).copied();
+
+        assert_eq!(usage("untagged").unwrap().bytes, 10);
+        assert_eq!(usage("mesh").unwrap().bytes, 33);
+        assert_eq!(usage("mesh").unwrap().allocations, 3);
+        assert_eq!(usage("fs").unwrap().bytes, 15);
+
+        let text = report.to_string();
+        assert!(text.starts_with("arena: 1024 bytes in 1 block(s)"));
+        assert!(text.contains("mesh"));
+    }
+
+    #[test]
+    fn test_tags_without_diagnostics() {
+        let arena = Arena::new(1024);
+
+        {
+            let _mesh = arena.tag("mesh");
+            let _a = arena.allocate::<u8>(10).unwrap();
+        }
+
+        assert!(arena.report().tags.is_empty());
+        assert_eq!(arena.high_water(), 10);
+    }
+
+    #[test]
+    fn test_poison() {
+        let mut arena = Arena::new(64);
+        arena.enable_diagnostics(DiagnosticOptions {
+            poison: true,
+            canaries: false,
+        });
+
+        let mut a = arena.allocate::<u8>(16).unwrap();
+        a.fill(1);
+        let mark = arena.mark();
+        let mut b = arena.allocate::<u8>(16).unwrap();
+        b.fill(2);
+
+        arena.reset_to(mark);
+        assert!(arena.data.as_slice()[..16].iter().all(|byte| *byte == 1));
+        assert!(arena.data.as_slice()[16..32]
+            .iter()
+            .all(|byte| *byte == POISON));
+
+        arena.clear();
+        assert!(arena.data.as_slice()[..32]
+            .iter()
+            .all(|byte| *byte == POISON));
+    }
+
+    #[test]
+    fn test_canaries() {
+        let mut arena = Arena::growable(64);
+        arena.enable_diagnostics(DiagnosticOptions {
+            poison: false,
+            canaries: true,
+        });
+
+        let a = arena.allocate::<u32>(4).unwrap();
+        let _b = arena.allocate::<u8>(100).unwrap();
+        assert_eq!(arena.occupied(), 16 + 8 + 100 + 8);
+
+        let canary = unsafe { core::slice::from_raw_parts(a.as_ptr().add(4) as *const u8, 8) };
+        assert_eq!(canary, CANARY);
+
+        arena.clear();
+        assert_eq!(arena.occupied(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "arena canary overwritten after a 4 byte allocation tagged \"mesh\"")]
+    fn test_canary_overwrite() {
+        let mut arena = Arena::new(64);
+        arena.enable_diagnostics(DiagnosticOptions {
+            poison: false,
+            canaries: true,
+        });
+
+        let _tag = arena.tag("mesh");
+        let mut a = arena.allocate::<u8>(4).unwrap();
+
+        unsafe {
+            a.as_mut_ptr().add(4).write(0);
+        }
+
+        arena.clear();
+    }
+}
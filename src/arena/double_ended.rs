@@ -0,0 +1,140 @@
+use super::{diagnostics, AllocError, Arena, ArenaSlice, ArenaString, Memory};
+
+// The top end of the first block serves short-lived allocations that are
+// released together with `clear_top`, independently of the bottom end.
+impl Arena {
+    fn bump_top(&self, size: usize, align: usize) -> Option<*mut u8> {
+        // Reserved arenas commit pages from the bottom up only.
+        if let Memory::Virtual(_) = self.data {
+            return None;
+        }
+
+        let base = self.data.as_ptr() as usize;
+        let start = (base + self.top.get()).checked_sub(size)? & !(align - 1);
+
+        if start < base + self.offset.get() {
+            return None;
+        }
+
+        self.top.set(start - base);
+        Some(start as *mut u8)
+    }
+
+    pub fn allocate_top<T: Copy>(&self, len: usize) -> Result<ArenaSlice<T>, AllocError> {
+        let align = core::mem::align_of::<T>();
+        let size = core::mem::size_of::<T>()
+            .checked_mul(len)
+            .ok_or(self.alloc_error(usize::MAX, align))?;
+
+        let ptr = if size == 0 {
+            core::ptr::NonNull::dangling().as_ptr()
+        } else {
+            self.bump_top(size, align).ok_or(AllocError {
+                requested: size,
+                align,
+                remaining: self.top.get() - self.offset.get(),
+            })? as *mut T
+        };
+
+        Ok(self.stamped_slice(ptr, len, true))
+    }
+
+    pub fn push_top<T: Copy>(&self, value: T) -> Result<ArenaSlice<T>, AllocError> {
+        let mut slice = self.allocate_top::<T>(1)?;
+
+        unsafe {
+            slice.as_mut_ptr().write(value);
+        }
+
+        Ok(slice)
+    }
+
+    pub fn push_slice_top<T: Copy>(&self, values: &[T]) -> Result<ArenaSlice<T>, AllocError> {
+        let mut slice = self.allocate_top::<T>(values.len())?;
+        slice.copy_from_slice(values);
+        Ok(slice)
+    }
+
+    pub fn push_string_top(&self, string: &str) -> Result<ArenaString, AllocError> {
+        let inner = self.push_slice_top(string.as_bytes())?;
+
+        Ok(ArenaString {
+            inner,
+            len: string.len(),
+        })
+    }
+
+    pub fn top_occupied(&self) -> usize {
+        self.data.len() - self.top.get()
+    }
+
+    pub fn clear_top(&self) {
+        let top = self.top.get();
+        let end = self.data.len();
+
+        if self.diagnostics.as_ref().is_some_and(|d| d.poison()) {
+            unsafe {
+                core::ptr::write_bytes(self.data.as_ptr().add(top), diagnostics::POISON, end - top);
+            }
+        }
+
+        self.top.set(end);
+
+        #[cfg(debug_assertions)]
+        self.liveness.top_cleared();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_double_ended_arena() {
+        let arena = Arena::new(64);
+
+        let persistent = arena.push_slice(&[1u32, 2, 3, 4]).unwrap();
+        let temporary = arena.push_slice_top(&[5u64, 6, 7]).unwrap();
+        assert_eq!(arena.occupied(), 16);
+        assert_eq!(arena.top_occupied(), 24);
+        assert_eq!(temporary.as_ptr() as usize % 8, 0);
+        assert_eq!(&temporary[..], &[5, 6, 7]);
+
+        let text = arena.push_string_top("scratch").unwrap();
+        assert_eq!(text, "scratch");
+
+        // The two ends meet.
+        assert!(arena.allocate::<u8>(32).is_err());
+        assert!(arena.allocate_top::<u8>(32).is_err());
+        assert!(arena.allocate::<u8>(8).is_ok());
+
+        arena.clear_top();
+        assert_eq!(arena.top_occupied(), 0);
+        assert_eq!(&persistent[..], &[1, 2, 3, 4]);
+        assert!(persistent.is_live());
+        #[cfg(debug_assertions)]
+        assert!(!temporary.is_live());
+
+        assert!(arena.allocate::<u8>(40).is_ok());
+        assert!(arena.is_full());
+
+        arena.clear();
+        assert_eq!(arena.occupied(), 0);
+        assert_eq!(arena.push_top(1u8).unwrap()[0], 1);
+        assert_eq!(arena.top_occupied(), 1);
+    }
+
+    #[test]
+    fn test_double_ended_growable_arena() {
+        let arena = Arena::growable(32);
+
+        arena.allocate_top::<u8>(24).unwrap();
+        arena.allocate::<u8>(16).unwrap();
+        assert_eq!(arena.blocks(), 2);
+        assert_eq!(arena.wasted(), 8);
+
+        arena.clear_top();
+        arena.allocate_top::<u8>(32).unwrap();
+        assert!(arena.allocate_top::<u8>(1).is_err());
+    }
+}
@@ -0,0 +1,106 @@
+use super::Arena;
+use core::cell::Cell;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FrameStats {
+    pub frame: u64,
+    pub used: usize,
+    pub peak: usize,
+    pub blocks: usize,
+}
+
+pub struct FrameArena {
+    arenas: [Arena; 2],
+    current: Cell<usize>,
+    frame: Cell<u64>,
+    peak: Cell<usize>,
+    stats: Cell<FrameStats>,
+}
+
+impl FrameArena {
+    pub fn new(size: usize) -> FrameArena {
+        FrameArena {
+            arenas: [Arena::growable(size), Arena::growable(size)],
+            current: Cell::new(0),
+            frame: Cell::new(0),
+            peak: Cell::new(0),
+            stats: Cell::new(FrameStats::default()),
+        }
+    }
+
+    pub fn current(&self) -> &Arena {
+        &self.arenas[self.current.get()]
+    }
+
+    pub fn previous(&self) -> &Arena {
+        &self.arenas[1 - self.current.get()]
+    }
+
+    pub fn frame(&self) -> u64 {
+        self.frame.get()
+    }
+
+    pub fn used(&self) -> usize {
+        self.current().occupied()
+    }
+
+    pub fn stats(&self) -> FrameStats {
+        self.stats.get()
+    }
+
+    // Ends the current frame. Its allocations stay readable through `previous`
+    // for one more frame, the frame before that is released.
+    pub fn swap(&self) {
+        let current = self.current();
+        let used = current.occupied();
+        self.peak.set(self.peak.get().max(used));
+
+        self.stats.set(FrameStats {
+            frame: self.frame.get(),
+            used,
+            peak: self.peak.get(),
+            blocks: current.blocks(),
+        });
+
+        self.previous().clear();
+        self.current.set(1 - self.current.get());
+        self.frame.set(self.frame.get() + 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_arena() {
+        let frames = FrameArena::new(1024);
+
+        let first = frames.current().push_slice(b"frame 0").unwrap();
+        assert_eq!(frames.used(), 7);
+
+        frames.swap();
+        assert_eq!(frames.frame(), 1);
+        assert_eq!(frames.stats().frame, 0);
+        assert_eq!(frames.stats().used, 7);
+        assert_eq!(&first[..], b"frame 0");
+        assert_eq!(frames.used(), 0);
+
+        let second = frames.current().allocate::<u8>(100).unwrap();
+        assert_eq!(frames.previous().occupied(), 7);
+
+        frames.swap();
+        #[cfg(debug_assertions)]
+        assert!(!first.is_live());
+        assert!(second.is_live());
+        assert_eq!(frames.stats().used, 100);
+        assert_eq!(frames.stats().peak, 100);
+
+        frames.swap();
+        #[cfg(debug_assertions)]
+        assert!(!second.is_live());
+        assert_eq!(frames.stats().used, 0);
+        assert_eq!(frames.stats().peak, 100);
+        assert_eq!(frames.frame(), 3);
+    }
+}
@@ -0,0 +1,162 @@
+use super::ArenaMark;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::cell::Cell;
+use core::cmp::Ordering;
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering as AtomicOrdering};
+use std::sync::{Mutex, PoisonError};
+
+// Debug-only bookkeeping behind the ArenaSlice checks. Every arena shares it
+// with the stamps of its slices, so a slice can still tell that its arena was
+// dropped, and a slice that nothing happened to since its last check only
+// costs one atomic load.
+#[derive(Debug, Default)]
+pub(super) struct Liveness {
+    // Bumped by every clear, rewind and drop.
+    version: AtomicU64,
+    generation: AtomicU32,
+    top_generation: AtomicU32,
+    dropped: AtomicBool,
+    // Rewinds since the last clear as (version, mark) pairs. Both increase from
+    // front to back, a rewind drops the entries it goes below.
+    rewinds: Mutex<Vec<(u64, ArenaMark)>>,
+}
+
+// What a slice was allocated in. It never takes part in comparisons so that
+// debug and release builds order slices alike.
+#[derive(Debug)]
+pub(super) struct Stamp {
+    arena: Arc<Liveness>,
+    generation: u32,
+    version: u64,
+    // The arena's version when the slice was last found live.
+    checked: Cell<u64>,
+    // Where the slice ends in the arena. Slices from the top end and empty
+    // slices have no position and only go away with a clear.
+    end: Option<ArenaMark>,
+    top: bool,
+}
+
+pub(super) enum Dead {
+    Dropped,
+    Cleared { allocated: u32, current: u32 },
+    Rewound,
+}
+
+impl PartialEq for Stamp {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for Stamp {}
+
+impl PartialOrd for Stamp {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Stamp {
+    fn cmp(&self, _other: &Self) -> Ordering {
+        Ordering::Equal
+    }
+}
+
+impl Liveness {
+    pub(super) fn new() -> Arc<Liveness> {
+        Arc::new(Liveness::default())
+    }
+
+    fn bump(&self) {
+        self.version.fetch_add(1, AtomicOrdering::Release);
+    }
+
+    fn rewinds(&self) -> std::sync::MutexGuard<'_, Vec<(u64, ArenaMark)>> {
+        self.rewinds.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub(super) fn dropped(&self) {
+        self.dropped.store(true, AtomicOrdering::Relaxed);
+        self.bump();
+    }
+
+    pub(super) fn cleared(&self) {
+        self.generation.fetch_add(1, AtomicOrdering::Relaxed);
+        self.rewinds().clear();
+        self.bump();
+    }
+
+    pub(super) fn top_cleared(&self) {
+        self.top_generation.fetch_add(1, AtomicOrdering::Relaxed);
+        self.bump();
+    }
+
+    pub(super) fn rewound(&self, mark: ArenaMark) {
+        let mut rewinds = self.rewinds();
+        let version = self.version.load(AtomicOrdering::Relaxed) + 1;
+
+        while rewinds.last().is_some_and(|(_, last)| *last >= mark) {
+            rewinds.pop();
+        }
+
+        rewinds.push((version, mark));
+        self.bump();
+    }
+
+    fn generation(&self, top: bool) -> u32 {
+        if top {
+            self.top_generation.load(AtomicOrdering::Relaxed)
+        } else {
+            self.generation.load(AtomicOrdering::Relaxed)
+        }
+    }
+}
+
+pub(super) fn stamp(arena: &Arc<Liveness>, end: Option<ArenaMark>, top: bool) -> Stamp {
+    let version = arena.version.load(AtomicOrdering::Acquire);
+
+    Stamp {
+        arena: arena.clone(),
+        generation: arena.generation(top),
+        version,
+        checked: Cell::new(version),
+        end,
+        top,
+    }
+}
+
+pub(super) fn check(stamp: &Stamp) -> Result<(), Dead> {
+    let arena = &stamp.arena;
+    let version = arena.version.load(AtomicOrdering::Acquire);
+
+    if version == stamp.checked.get() {
+        return Ok(());
+    }
+
+    if arena.dropped.load(AtomicOrdering::Relaxed) {
+        return Err(Dead::Dropped);
+    }
+
+    let current = arena.generation(stamp.top);
+
+    if current != stamp.generation {
+        return Err(Dead::Cleared {
+            allocated: stamp.generation,
+            current,
+        });
+    }
+
+    // The first rewind after the allocation went the furthest down.
+    if let Some(end) = stamp.end {
+        let rewinds = arena.rewinds();
+        let later = rewinds.partition_point(|(version, _)| *version <= stamp.version);
+
+        if rewinds.get(later).is_some_and(|(_, mark)| *mark < end) {
+            return Err(Dead::Rewound);
+        }
+    }
+
+    stamp.checked.set(version);
+    Ok(())
+}
@@ -0,0 +1,500 @@
+use super::{AllocError, Arena, ArenaSlice};
+use crate::hash;
+use core::borrow::Borrow;
+use core::hash::Hash;
+
+enum Slot<K, V> {
+    Empty,
+    Tombstone,
+    Full { hash: u64, key: K, value: V },
+}
+
+// Entries are dropped by the arena when it releases their table, the same way
+// as values pushed into it, so a map outliving a clear never touches freed
+// memory on drop.
+pub struct ArenaMap<K, V> {
+    buckets: Option<ArenaSlice<Slot<K, V>>>,
+    len: usize,
+    tombstones: usize,
+}
+
+// Fibonacci hashing constant, 2^64 divided by the golden ratio.
+const GOLDEN: u64 = 0x9e37_79b9_7f4a_7c15;
+
+// The bucket a hash starts probing at. Fx hashes leave the low bits weak for
+// strided keys such as pointers and ids, and the high bits weak for small
+// sequential ones, so the hash is mixed once more before the top bits are used.
+fn home(hash: u64, buckets: usize) -> usize {
+    ((hash ^ (hash >> 32)).wrapping_mul(GOLDEN) >> (64 - buckets.trailing_zeros())) as usize
+}
+
+impl<K: Hash + Eq, V> ArenaMap<K, V> {
+    pub fn new() -> Self {
+        ArenaMap {
+            buckets: None,
+            len: 0,
+            tombstones: 0,
+        }
+    }
+
+    pub fn with_capacity(arena: &Arena, capacity: usize) -> Result<Self, AllocError> {
+        let mut map = ArenaMap::new();
+        map.reserve(arena, capacity)?;
+        Ok(map)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.slots().len() / 4 * 3
+    }
+
+    fn slots(&self) -> &[Slot<K, V>] {
+        match &self.buckets {
+            Some(buckets) => buckets,
+            None => &[],
+        }
+    }
+
+    pub fn reserve(&mut self, arena: &Arena, additional: usize) -> Result<(), AllocError> {
+        let overflow = || arena.alloc_error(usize::MAX, core::mem::align_of::<Slot<K, V>>());
+        let required = self.len.checked_add(additional).ok_or_else(overflow)?;
+
+        if required + self.tombstones <= self.capacity() {
+            return Ok(());
+        }
+
+        let buckets = (required.checked_mul(4).ok_or_else(overflow)? / 3 + 1)
+            .checked_next_power_of_two()
+            .ok_or_else(overflow)?
+            .max(8);
+
+        // Only tombstones are in the way, the table is reused instead of being
+        // abandoned in the arena for a new one.
+        if buckets <= self.slots().len() {
+            self.purge_tombstones();
+            return Ok(());
+        }
+
+        self.rehash(arena, buckets)
+    }
+
+    // Turns tombstones back into empty slots. Entries are reinserted in probe
+    // order starting after a slot that was already empty, which no probe chain
+    // crosses, so each lands on or before its old slot and none is cut off.
+    fn purge_tombstones(&mut self) {
+        let Some(buckets) = self.buckets.as_mut() else {
+            return;
+        };
+
+        let count = buckets.len();
+        let mask = count - 1;
+        let start = buckets
+            .iter()
+            .position(|slot| matches!(slot, Slot::Empty))
+            .expect("tables are never full");
+
+        for slot in buckets.iter_mut() {
+            if matches!(slot, Slot::Tombstone) {
+                *slot = Slot::Empty;
+            }
+        }
+
+        for step in 1..count {
+            let index = (start + step) & mask;
+            let Slot::Full { hash, .. } = buckets[index] else {
+                continue;
+            };
+
+            let entry = core::mem::replace(&mut buckets[index], Slot::Empty);
+            let mut target = home(hash, count);
+
+            while !matches!(buckets[target], Slot::Empty) {
+                target = (target + 1) & mask;
+            }
+
+            buckets[target] = entry;
+        }
+
+        self.tombstones = 0;
+    }
+
+    fn rehash(&mut self, arena: &Arena, count: usize) -> Result<(), AllocError> {
+        let mark = arena.mark();
+        let mut buckets = arena.allocate::<Slot<K, V>>(count)?;
+
+        for i in 0..count {
+            unsafe {
+                buckets.as_mut_ptr().add(i).write(Slot::Empty);
+            }
+        }
+
+        if core::mem::needs_drop::<Slot<K, V>>() {
+            arena.register_drop(mark, buckets.as_mut_ptr(), count)?;
+        }
+
+        let mask = count - 1;
+
+        // The old table is abandoned in the arena with only empty slots left for
+        // its destructor record.
+        if let Some(mut old) = self.buckets.take() {
+            for slot in old.iter_mut() {
+                if let Slot::Full { hash, .. } = slot {
+                    let mut index = home(*hash, count);
+
+                    while !matches!(buckets[index], Slot::Empty) {
+                        index = (index + 1) & mask;
+                    }
+
+                    buckets[index] = core::mem::replace(slot, Slot::Empty);
+                }
+            }
+        }
+
+        self.buckets = Some(buckets);
+        self.tombstones = 0;
+        Ok(())
+    }
+
+    fn find<Q>(&self, hash: u64, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let slots = self.slots();
+
+        if slots.is_empty() {
+            return None;
+        }
+
+        let mask = slots.len() - 1;
+        let mut index = home(hash, slots.len());
+
+        loop {
+            match &slots[index] {
+                Slot::Empty => return None,
+                Slot::Full {
+                    hash: slot_hash,
+                    key: slot_key,
+                    ..
+                } if *slot_hash == hash && slot_key.borrow() == key => return Some(index),
+                _ => {}
+            }
+
+            index = (index + 1) & mask;
+        }
+    }
+
+    // Slots inspected by a successful lookup, for checking how well the table
+    // spreads its keys.
+    #[cfg(test)]
+    pub(crate) fn probes<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = hash::hash(key);
+        let index = self.find(hash, key)?;
+        let mask = self.slots().len() - 1;
+
+        Some((index.wrapping_sub(home(hash, mask + 1)) & mask) + 1)
+    }
+
+    pub fn insert(&mut self, arena: &Arena, key: K, value: V) -> Result<Option<V>, AllocError> {
+        let hash = hash::hash(&key);
+
+        if let Some(index) = self.find(hash, &key) {
+            if let Some(Slot::Full { value: old, .. }) =
+                self.buckets.as_mut().map(|buckets| &mut buckets[index])
+            {
+                return Ok(Some(core::mem::replace(old, value)));
+            }
+        }
+
+        self.reserve(arena, 1)?;
+
+        let buckets = self.buckets.as_mut().expect("reserve allocates the table");
+        let mask = buckets.len() - 1;
+        let mut index = home(hash, buckets.len());
+
+        loop {
+            match buckets[index] {
+                Slot::Empty => break,
+                Slot::Tombstone => {
+                    self.tombstones -= 1;
+                    break;
+                }
+                Slot::Full { .. } => index = (index + 1) & mask,
+            }
+        }
+
+        buckets[index] = Slot::Full { hash, key, value };
+        self.len += 1;
+
+        Ok(None)
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_key_value(key).map(|(_, value)| value)
+    }
+
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.find(hash::hash(key), key)?;
+
+        match &self.slots()[index] {
+            Slot::Full { key, value, .. } => Some((key, value)),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.find(hash::hash(key), key)?;
+
+        match &mut self.buckets.as_mut()?[index] {
+            Slot::Full { value, .. } => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.find(hash::hash(key), key).is_some()
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.find(hash::hash(key), key)?;
+        let buckets = self.buckets.as_mut()?;
+
+        match core::mem::replace(&mut buckets[index], Slot::Tombstone) {
+            Slot::Full { value, .. } => {
+                self.len -= 1;
+                self.tombstones += 1;
+                Some(value)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        if let Some(buckets) = self.buckets.as_mut() {
+            for slot in buckets.iter_mut() {
+                *slot = Slot::Empty;
+            }
+        }
+
+        self.len = 0;
+        self.tombstones = 0;
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.slots().iter().filter_map(|slot| match slot {
+            Slot::Full { key, value, .. } => Some((key, value)),
+            _ => None,
+        })
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, value)| value)
+    }
+}
+
+impl<K: Hash + Eq, V> Default for ArenaMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::rc::Rc;
+
+    #[test]
+    fn test_arena_map() {
+        let arena = Arena::new(4096);
+        let mut map = ArenaMap::new();
+
+        assert_eq!(map.get("missing"), None);
+        assert_eq!(map.insert(&arena, "a", 1).unwrap(), None);
+        assert_eq!(map.insert(&arena, "b", 2).unwrap(), None);
+        assert_eq!(map.insert(&arena, "a", 3).unwrap(), Some(1));
+
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get("a"), Some(&3));
+        assert_eq!(map.get("b"), Some(&2));
+        assert!(map.contains_key("b"));
+
+        *map.get_mut("b").unwrap() += 10;
+        assert_eq!(map.remove("b"), Some(12));
+        assert_eq!(map.remove("b"), None);
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.iter().count(), 1);
+
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(map.get("a"), None);
+    }
+
+    #[test]
+    fn test_arena_map_growth() {
+        let arena = Arena::growable(1024);
+        let mut map = ArenaMap::with_capacity(&arena, 4).unwrap();
+
+        for i in 0..1000u32 {
+            map.insert(&arena, i, i * 2).unwrap();
+        }
+
+        for i in (0..1000u32).step_by(2) {
+            assert_eq!(map.remove(&i), Some(i * 2));
+        }
+
+        for i in 1000..1500u32 {
+            map.insert(&arena, i, i * 2).unwrap();
+        }
+
+        assert_eq!(map.len(), 1000);
+        assert!(map.capacity() >= map.len());
+
+        for i in 0..1500u32 {
+            let expected = if i < 1000 && i % 2 == 0 {
+                None
+            } else {
+                Some(i * 2)
+            };
+            assert_eq!(map.get(&i).copied(), expected);
+        }
+
+        let sum: u64 = map.values().map(|value| *value as u64).sum();
+        assert_eq!(sum, map.keys().map(|key| *key as u64 * 2).sum());
+    }
+
+    #[test]
+    fn test_arena_map_strided_keys() {
+        let arena = Arena::growable(64 * 1024);
+
+        for stride in [1u64, 64, 4096, 1 << 32] {
+            let mut map = ArenaMap::new();
+
+            for i in 0..10_000u64 {
+                map.insert(&arena, i * stride, i).unwrap();
+            }
+
+            let probes = (0..10_000u64)
+                .map(|i| map.probes(&(i * stride)).unwrap())
+                .sum::<usize>();
+            let mean = probes as f64 / 10_000.0;
+
+            assert!(
+                mean < 4.0,
+                "{} probes per lookup at stride {}",
+                mean,
+                stride
+            );
+        }
+    }
+
+    #[test]
+    fn test_arena_map_churn() {
+        let arena = Arena::new(64 * 1024);
+        let mut map = ArenaMap::new();
+
+        for i in 0..16u32 {
+            map.insert(&arena, i, i).unwrap();
+        }
+
+        let occupied = arena.occupied();
+
+        for i in 16..100_000u32 {
+            map.insert(&arena, i, i).unwrap();
+            assert_eq!(map.remove(&(i - 16)), Some(i - 16));
+        }
+
+        assert_eq!(arena.occupied(), occupied);
+        assert_eq!(map.len(), 16);
+
+        for i in 0..100_000u32 {
+            let expected = (i >= 100_000 - 16).then_some(i);
+            assert_eq!(map.get(&i).copied(), expected);
+        }
+    }
+
+    #[test]
+    fn test_arena_map_drops_entries() {
+        let counter = Rc::new(());
+        let arena = Arena::new(4096);
+
+        {
+            let mut map = ArenaMap::new();
+
+            for i in 0..20u32 {
+                map.insert(&arena, i, counter.clone()).unwrap();
+            }
+
+            assert_eq!(Rc::strong_count(&counter), 21);
+            drop(map.remove(&0));
+            assert_eq!(Rc::strong_count(&counter), 20);
+        }
+
+        assert_eq!(Rc::strong_count(&counter), 20);
+        arena.clear();
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    fn test_arena_map_outlives_clear() {
+        let counter = Rc::new(());
+        let arena = Arena::new(4096);
+
+        {
+            let mut map = ArenaMap::new();
+
+            for i in 0..4u32 {
+                map.insert(&arena, i, counter.clone()).unwrap();
+            }
+
+            arena.clear();
+            assert_eq!(Rc::strong_count(&counter), 1);
+            arena.push_slice(&[0xffu8; 4096]).unwrap();
+        }
+
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    fn test_arena_map_full_arena() {
+        let arena = Arena::new(16);
+        let mut map = ArenaMap::new();
+
+        assert!(map.insert(&arena, 1u32, 1u32).is_err());
+        assert!(map.is_empty());
+    }
+}
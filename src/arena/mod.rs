@@ -0,0 +1,1330 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cell::{Cell, RefCell};
+use core::cmp::Ordering;
+use core::fmt::Write;
+use core::ops::{Deref, DerefMut};
+
+mod allocator;
+mod atomic;
+mod diagnostics;
+mod double_ended;
+mod frame;
+#[cfg(debug_assertions)]
+mod liveness;
+mod map;
+mod pool;
+mod snapshot;
+mod vec;
+mod vmem;
+
+pub use allocator::{ArenaAllocator, BumpAllocator};
+pub use atomic::{AtomicArena, LocalArena};
+pub use diagnostics::{ArenaReport, DiagnosticOptions, TagScope, TagUsage};
+pub use frame::{FrameArena, FrameStats};
+pub use map::ArenaMap;
+pub use pool::{Handle, Pool};
+pub use snapshot::{Pod, RelPtr, RelSlice, SnapshotError};
+pub use vec::ArenaVec;
+
+pub struct Arena {
+    data: Memory,
+    offset: Cell<usize>,
+    top: Cell<usize>,
+    block_size: usize,
+    #[allow(clippy::vec_box)]
+    blocks: RefCell<Vec<Box<Arena>>>,
+    current: Cell<usize>,
+    generation: Cell<u32>,
+    #[cfg(debug_assertions)]
+    liveness: alloc::sync::Arc<liveness::Liveness>,
+    drops: Cell<*mut DropRecord>,
+    diagnostics: Option<Box<diagnostics::Diagnostics>>,
+}
+
+enum Memory {
+    Heap(Box<[u8]>),
+    Virtual(vmem::VirtualMemory),
+    Mapped(snapshot::Mapping),
+}
+
+impl Memory {
+    fn as_ptr(&self) -> *mut u8 {
+        match self {
+            Memory::Heap(data) => data.as_ptr() as *mut u8,
+            Memory::Virtual(memory) => memory.as_ptr(),
+            Memory::Mapped(mapping) => mapping.as_ptr(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Memory::Heap(data) => data.len(),
+            Memory::Virtual(memory) => memory.len(),
+            Memory::Mapped(mapping) => mapping.len(),
+        }
+    }
+
+    fn committed(&self) -> usize {
+        match self {
+            Memory::Heap(data) => data.len(),
+            Memory::Virtual(memory) => memory.committed(),
+            Memory::Mapped(mapping) => mapping.len(),
+        }
+    }
+
+    fn commit(&self, end: usize) -> bool {
+        match self {
+            Memory::Heap(data) => end <= data.len(),
+            Memory::Virtual(memory) => memory.commit(end),
+            Memory::Mapped(mapping) => end <= mapping.len(),
+        }
+    }
+
+    #[cfg(test)]
+    fn as_slice(&self) -> &[u8] {
+        unsafe { core::slice::from_raw_parts(self.as_ptr(), self.committed()) }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AllocError {
+    pub requested: usize,
+    pub align: usize,
+    pub remaining: usize,
+}
+
+impl core::fmt::Display for AllocError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.requested == usize::MAX {
+            return write!(f, "arena allocation size overflowed (align {})", self.align);
+        }
+
+        write!(
+            f,
+            "arena out of memory: requested {} bytes (align {}) with {} bytes remaining",
+            self.requested, self.align, self.remaining
+        )
+    }
+}
+
+impl std::error::Error for AllocError {}
+
+// Destructors for values that need dropping are kept as a linked list of records
+// allocated in the arena itself, newest first, so they run in reverse order.
+struct DropRecord {
+    prev: *mut DropRecord,
+    mark: ArenaMark,
+    drop: unsafe fn(*mut u8, usize),
+    ptr: *mut u8,
+    len: usize,
+}
+
+// Blocks of growable arenas are sized by the request, so a huge request has to
+// fail instead of aborting inside the global allocator.
+fn heap(size: usize) -> Option<Memory> {
+    let mut data = Vec::new();
+    data.try_reserve_exact(size).ok()?;
+    data.resize(size, 0);
+    Some(Memory::Heap(data.into_boxed_slice()))
+}
+
+unsafe fn drop_slice<T>(ptr: *mut u8, len: usize) {
+    core::ptr::drop_in_place(core::ptr::slice_from_raw_parts_mut(ptr as *mut T, len));
+}
+
+#[derive(Debug, PartialEq, PartialOrd, Eq, Ord)]
+pub struct ArenaSlice<T> {
+    arena: *const Arena,
+    ptr: *mut T,
+    len: usize,
+    #[cfg(debug_assertions)]
+    stamp: liveness::Stamp,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ArenaMark {
+    block: usize,
+    offset: usize,
+}
+
+pub struct ScratchScope<'a> {
+    arena: &'a Arena,
+    mark: ArenaMark,
+}
+
+#[derive(Debug, Clone, Eq)]
+pub struct ArenaString {
+    inner: ArenaSlice<u8>,
+    len: usize,
+}
+
+impl<T> ArenaSlice<T> {
+    #[inline]
+    fn check(&self) {
+        #[cfg(debug_assertions)]
+        match liveness::check(&self.stamp) {
+            Ok(()) => {}
+            Err(liveness::Dead::Dropped) => panic!("ArenaSlice used after its arena was dropped"),
+            Err(liveness::Dead::Cleared { allocated, current }) => panic!(
+                "ArenaSlice used after its arena was cleared (allocated in generation {}, arena is at {})",
+                allocated, current
+            ),
+            Err(liveness::Dead::Rewound) => {
+                panic!("ArenaSlice used after its arena was reset below it")
+            }
+        }
+    }
+
+    pub fn is_live(&self) -> bool {
+        #[cfg(debug_assertions)]
+        {
+            liveness::check(&self.stamp).is_ok()
+        }
+
+        #[cfg(not(debug_assertions))]
+        {
+            true
+        }
+    }
+}
+
+impl<T> Deref for ArenaSlice<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        self.check();
+        unsafe { core::slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl<T> DerefMut for ArenaSlice<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.check();
+        unsafe { core::slice::from_raw_parts_mut(self.ptr, self.len) }
+    }
+}
+
+impl<T> AsRef<[T]> for ArenaSlice<T> {
+    fn as_ref(&self) -> &[T] {
+        self.deref()
+    }
+}
+
+impl<T: Clone> Clone for ArenaSlice<T> {
+    fn clone(&self) -> Self {
+        unsafe { (*self.arena).push_slice(&self[..]).unwrap() }
+    }
+}
+
+impl Deref for ArenaString {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { core::str::from_utf8_unchecked(&self.inner[..self.len]) }
+    }
+}
+
+impl DerefMut for ArenaString {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { core::str::from_utf8_unchecked_mut(&mut self.inner[..self.len]) }
+    }
+}
+
+impl AsRef<str> for ArenaString {
+    fn as_ref(&self) -> &str {
+        self.deref()
+    }
+}
+
+impl PartialEq for ArenaString {
+    fn eq(&self, other: &Self) -> bool {
+        self.deref() == other.deref()
+    }
+}
+
+impl PartialOrd for ArenaString {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.deref().partial_cmp(other.deref())
+    }
+}
+
+impl PartialEq<str> for ArenaString {
+    fn eq(&self, other: &str) -> bool {
+        self.deref() == other
+    }
+}
+
+impl PartialEq<&str> for ArenaString {
+    fn eq(&self, other: &&str) -> bool {
+        self.deref() == *other
+    }
+}
+
+impl core::fmt::Display for ArenaString {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self)
+    }
+}
+
+impl Write for ArenaString {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        self.push_str(s).ok_or(core::fmt::Error)
+    }
+}
+
+impl ArenaString {
+    pub fn from_slice(view: ArenaSlice<u8>) -> ArenaString {
+        let len = view.len();
+
+        ArenaString { inner: view, len }
+    }
+
+    pub fn as_str(&self) -> &str {
+        self
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.capacity() - self.len
+    }
+
+    pub fn push_str(&mut self, s: &str) -> Option<()> {
+        if s.len() > self.remaining() {
+            return None;
+        }
+
+        self.inner[self.len..self.len + s.len()].copy_from_slice(s.as_bytes());
+        self.len += s.len();
+        Some(())
+    }
+
+    pub fn push(&mut self, c: char) -> Option<()> {
+        self.push_str(c.encode_utf8(&mut [0; 4]))
+    }
+
+    // Truncation never splits a character: the new length is rounded down to
+    // the closest character boundary.
+    pub fn truncate(&mut self, len: usize) {
+        if len >= self.len {
+            return;
+        }
+
+        let mut len = len;
+
+        while !self.is_char_boundary(len) {
+            len -= 1;
+        }
+
+        self.len = len;
+    }
+
+    pub fn pop(&mut self) -> Option<char> {
+        let c = self.chars().next_back()?;
+        self.len -= c.len_utf8();
+        Some(c)
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+// Measures formatted output without writing it anywhere.
+struct Counter(usize);
+
+impl Write for Counter {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        self.0 += s.len();
+        Ok(())
+    }
+}
+
+#[macro_export]
+macro_rules! arena_format {
+    ($arena:expr, $($arg:tt)*) => {
+        $crate::arena::Arena::format($arena, core::format_args!($($arg)*))
+    };
+}
+
+impl ArenaMark {
+    pub fn block(&self) -> usize {
+        self.block
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl<'a> ScratchScope<'a> {
+    pub fn mark(&self) -> ArenaMark {
+        self.mark
+    }
+
+    // Tied to the scope so that the arena can't be held on to past the rewind.
+    pub fn arena(&self) -> &Arena {
+        self.arena
+    }
+}
+
+impl Deref for ScratchScope<'_> {
+    type Target = Arena;
+
+    fn deref(&self) -> &Self::Target {
+        self.arena
+    }
+}
+
+impl Drop for ScratchScope<'_> {
+    fn drop(&mut self) {
+        self.arena.reset_to(self.mark);
+    }
+}
+
+impl Arena {
+    pub fn new(size: usize) -> Arena {
+        Arena::with_memory(Memory::Heap(alloc::vec![0; size].into_boxed_slice()))
+    }
+
+    pub fn reserved(size: usize) -> Option<Arena> {
+        let memory = vmem::VirtualMemory::reserve(size)?;
+        Some(Arena::with_memory(Memory::Virtual(memory)))
+    }
+
+    fn with_memory(data: Memory) -> Arena {
+        Arena {
+            top: Cell::new(data.len()),
+            data,
+            offset: Cell::new(0),
+            block_size: 0,
+            blocks: RefCell::new(Vec::new()),
+            current: Cell::new(0),
+            generation: Cell::new(0),
+            #[cfg(debug_assertions)]
+            liveness: liveness::Liveness::new(),
+            drops: Cell::new(core::ptr::null_mut()),
+            diagnostics: None,
+        }
+    }
+
+    pub fn growable(block_size: usize) -> Arena {
+        let mut arena = Arena::new(block_size);
+        arena.block_size = block_size.max(1);
+        arena
+    }
+
+    pub fn set_release_on_clear(&mut self, release: bool) {
+        if let Memory::Virtual(memory) = &mut self.data {
+            memory.set_release_on_clear(release);
+        }
+    }
+
+    fn slice<T>(&self, ptr: *mut T, len: usize) -> ArenaSlice<T> {
+        self.stamped_slice(ptr, len, false)
+    }
+
+    #[cfg_attr(not(debug_assertions), allow(unused_variables))]
+    fn stamped_slice<T>(&self, ptr: *mut T, len: usize, top: bool) -> ArenaSlice<T> {
+        ArenaSlice {
+            arena: self,
+            ptr,
+            len,
+            #[cfg(debug_assertions)]
+            stamp: liveness::stamp(
+                &self.liveness,
+                (!top).then(|| self.position(ptr, len)).flatten(),
+                top,
+            ),
+        }
+    }
+
+    // The mark just past a slice, found from the block that holds it.
+    #[cfg(debug_assertions)]
+    fn position<T>(&self, ptr: *mut T, len: usize) -> Option<ArenaMark> {
+        let start = ptr as usize;
+        let end = start + core::mem::size_of::<T>() * len;
+
+        if end == start {
+            return None;
+        }
+
+        core::iter::once(self.current.get())
+            .chain(0..self.blocks())
+            .find_map(|index| {
+                let block = self.block(index);
+                let base = block.data.as_ptr() as usize;
+
+                (base <= start && end <= base + block.data.len()).then(|| ArenaMark {
+                    block: index,
+                    offset: end - base,
+                })
+            })
+    }
+
+    fn block(&self, index: usize) -> &Arena {
+        if index == 0 {
+            return self;
+        }
+
+        // Blocks are boxed and never removed before the arena is dropped, so the
+        // reference outlives the borrow of the block list.
+        let block: *const Arena = &*self.blocks.borrow()[index - 1];
+        unsafe { &*block }
+    }
+
+    fn bump(&self, size: usize, align: usize) -> Option<*mut u8> {
+        let base = self.data.as_ptr() as usize;
+        let offset = ((base + self.offset.get()).checked_add(align - 1)? & !(align - 1)) - base;
+        let new_offset = offset.checked_add(size)?;
+
+        if new_offset <= self.top.get() && self.data.commit(new_offset) {
+            self.offset.set(new_offset);
+            Some(unsafe { self.data.as_ptr().add(offset) })
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn alloc_error(&self, requested: usize, align: usize) -> AllocError {
+        let block = self.block(self.current.get());
+
+        AllocError {
+            requested,
+            align,
+            remaining: block.top.get() - block.offset.get(),
+        }
+    }
+
+    fn reserve(&self, size: usize, align: usize) -> Result<*mut u8, AllocError> {
+        // Zero-sized allocations take no space and only need a well-aligned pointer.
+        if size == 0 {
+            return Ok(align as *mut u8);
+        }
+
+        let Some(diagnostics) = &self.diagnostics else {
+            return self.reserve_block(size, align);
+        };
+
+        let guarded = size
+            .checked_add(diagnostics.guard_size())
+            .ok_or(self.alloc_error(usize::MAX, align))?;
+
+        let mark = self.mark();
+        let ptr = self.reserve_block(guarded, align)?;
+        diagnostics.record(mark, ptr, size, self.occupied());
+
+        Ok(ptr)
+    }
+
+    fn reserve_block(&self, size: usize, align: usize) -> Result<*mut u8, AllocError> {
+        let mut index = self.current.get();
+
+        loop {
+            if let Some(ptr) = self.block(index).bump(size, align) {
+                self.current.set(index);
+                return Ok(ptr);
+            }
+
+            if self.block_size == 0 {
+                return Err(self.alloc_error(size, align));
+            }
+
+            index += 1;
+
+            if index > self.blocks.borrow().len() {
+                let block = size
+                    .checked_add(align)
+                    .and_then(|capacity| heap(capacity.max(self.block_size)))
+                    .ok_or(self.alloc_error(size, align))?;
+                self.blocks
+                    .borrow_mut()
+                    .push(Box::new(Arena::with_memory(block)));
+            }
+        }
+    }
+
+    pub fn allocate<T>(&self, len: usize) -> Result<ArenaSlice<T>, AllocError> {
+        let align = core::mem::align_of::<T>();
+        let size = core::mem::size_of::<T>()
+            .checked_mul(len)
+            .ok_or(self.alloc_error(usize::MAX, align))?;
+        let ptr = self.reserve(size, align)? as *mut T;
+
+        Ok(self.slice(ptr, len))
+    }
+
+    pub fn allocate_string(&self, len: usize) -> Result<ArenaString, AllocError> {
+        let inner = self.allocate(len)?;
+
+        Ok(ArenaString { inner, len: 0 })
+    }
+
+    fn grow_in_place(&self, ptr: *mut u8, old_size: usize, new_size: usize) -> bool {
+        let block = self.block(self.current.get());
+        let base = block.data.as_ptr() as usize;
+        let offset = block.offset.get();
+
+        if (ptr as usize) < base || ptr as usize + old_size != base + offset {
+            return false;
+        }
+
+        match (ptr as usize - base).checked_add(new_size) {
+            Some(end) if end <= block.top.get() && block.data.commit(end) => {
+                block.offset.set(end);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn release_last(&self, ptr: *mut u8, size: usize) {
+        let block = self.block(self.current.get());
+        let base = block.data.as_ptr() as usize;
+
+        if ptr as usize >= base && ptr as usize + size == base + block.offset.get() {
+            block.offset.set(ptr as usize - base);
+        }
+    }
+
+    fn reserve_drop_record(&self) -> Result<*mut DropRecord, AllocError> {
+        let size = core::mem::size_of::<DropRecord>();
+        let align = core::mem::align_of::<DropRecord>();
+
+        self.reserve(size, align)
+            .map(|record| record as *mut DropRecord)
+    }
+
+    fn link_drop<T>(&self, record: *mut DropRecord, mark: ArenaMark, ptr: *mut T, len: usize) {
+        unsafe {
+            record.write(DropRecord {
+                prev: self.drops.get(),
+                mark,
+                drop: drop_slice::<T>,
+                ptr: ptr as *mut u8,
+                len,
+            });
+        }
+
+        self.drops.set(record);
+    }
+
+    fn register_drop<T>(&self, mark: ArenaMark, ptr: *mut T, len: usize) -> Result<(), AllocError> {
+        let record = self
+            .reserve_drop_record()
+            .inspect_err(|_| self.reset_to(mark))?;
+
+        self.link_drop(record, mark, ptr, len);
+        Ok(())
+    }
+
+    fn run_drops(&self, mark: ArenaMark) {
+        let mut record = self.drops.get();
+
+        while !record.is_null() && unsafe { (*record).mark >= mark } {
+            let DropRecord {
+                prev,
+                drop,
+                ptr,
+                len,
+                ..
+            } = unsafe { record.read() };
+
+            self.drops.set(prev);
+            unsafe { drop(ptr, len) };
+            record = prev;
+        }
+    }
+
+    pub fn push<T>(&self, value: T) -> Result<ArenaSlice<T>, AllocError> {
+        let size = core::mem::size_of::<T>();
+        let align = core::mem::align_of::<T>();
+        let mark = self.mark();
+        let ptr = self.reserve(size, align)? as *mut T;
+
+        if core::mem::needs_drop::<T>() {
+            self.register_drop(mark, ptr, 1)?;
+        }
+
+        unsafe {
+            ptr.write(value);
+        }
+
+        Ok(self.slice(ptr, 1))
+    }
+
+    pub fn push_slice<T: Clone>(&self, values: &[T]) -> Result<ArenaSlice<T>, AllocError> {
+        let align = core::mem::align_of::<T>();
+        let mark = self.mark();
+        let ptr = self.reserve(core::mem::size_of_val(values), align)? as *mut T;
+
+        if core::mem::needs_drop::<T>() {
+            for (i, value) in values.iter().enumerate() {
+                unsafe {
+                    ptr.add(i).write(value.clone());
+                }
+            }
+
+            if let Err(error) = self.register_drop(mark, ptr, values.len()) {
+                unsafe { drop_slice::<T>(ptr as *mut u8, values.len()) };
+                return Err(error);
+            }
+        } else {
+            unsafe {
+                ptr.copy_from_nonoverlapping(values.as_ptr(), values.len());
+            }
+        }
+
+        Ok(self.slice(ptr, values.len()))
+    }
+
+    pub fn push_string(&self, string: &str) -> Result<ArenaString, AllocError> {
+        let inner = self.push_slice(string.as_bytes())?;
+        Ok(ArenaString {
+            inner,
+            len: string.len(),
+        })
+    }
+
+    pub fn format(&self, args: core::fmt::Arguments) -> Result<ArenaString, AllocError> {
+        let mut counter = Counter(0);
+        counter
+            .write_fmt(args)
+            .expect("a formatting trait implementation returned an error");
+
+        let mut string = self.allocate_string(counter.0)?;
+        string
+            .write_fmt(args)
+            .expect("a formatting trait implementation returned an error");
+        Ok(string)
+    }
+
+    pub fn clear(&self) {
+        self.reset_to(ArenaMark {
+            block: 0,
+            offset: 0,
+        });
+
+        if let Memory::Virtual(memory) = &self.data {
+            memory.clear();
+        }
+
+        self.clear_top();
+        self.generation.set(self.generation.get().wrapping_add(1));
+
+        #[cfg(debug_assertions)]
+        self.liveness.cleared();
+
+        if let Some(diagnostics) = &self.diagnostics {
+            diagnostics.cleared();
+        }
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.generation.get()
+    }
+
+    pub fn mark(&self) -> ArenaMark {
+        let block = self.current.get();
+
+        ArenaMark {
+            block,
+            offset: self.block(block).offset.get(),
+        }
+    }
+
+    pub fn reset_to(&self, mark: ArenaMark) {
+        if mark > self.mark() {
+            return;
+        }
+
+        self.run_drops(mark);
+
+        #[cfg(debug_assertions)]
+        self.liveness.rewound(mark);
+
+        if let Some(diagnostics) = &self.diagnostics {
+            diagnostics.verify(mark);
+        }
+
+        let poison = self.diagnostics.as_ref().is_some_and(|d| d.poison());
+
+        for index in (mark.block..=self.current.get()).rev() {
+            let block = self.block(index);
+            let start = if index == mark.block { mark.offset } else { 0 };
+
+            if poison {
+                let freed = start..block.offset.get();
+                unsafe {
+                    core::ptr::write_bytes(
+                        block.data.as_ptr().add(freed.start),
+                        diagnostics::POISON,
+                        freed.len(),
+                    );
+                }
+            }
+
+            block.offset.set(start);
+        }
+
+        self.current.set(mark.block);
+    }
+
+    pub fn scratch(&self) -> ScratchScope<'_> {
+        ScratchScope {
+            arena: self,
+            mark: self.mark(),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        (0..self.blocks()).map(|i| self.block(i).data.len()).sum()
+    }
+
+    pub fn committed(&self) -> usize {
+        (0..self.blocks())
+            .map(|i| self.block(i).data.committed())
+            .sum()
+    }
+
+    pub fn occupied(&self) -> usize {
+        (0..self.blocks()).map(|i| self.block(i).offset.get()).sum()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.occupied() == self.size()
+    }
+
+    pub fn is_growable(&self) -> bool {
+        self.block_size != 0
+    }
+
+    pub fn blocks(&self) -> usize {
+        self.blocks.borrow().len() + 1
+    }
+
+    pub fn wasted(&self) -> usize {
+        (0..self.current.get())
+            .map(|i| {
+                let block = self.block(i);
+                block.top.get() - block.offset.get()
+            })
+            .sum()
+    }
+}
+
+impl Drop for Arena {
+    fn drop(&mut self) {
+        self.run_drops(ArenaMark {
+            block: 0,
+            offset: 0,
+        });
+
+        #[cfg(debug_assertions)]
+        self.liveness.dropped();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Copy, Clone)]
+    struct Point {
+        x: f64,
+        y: f64,
+    }
+
+    #[derive(Debug)]
+    struct Entity {
+        position: Point,
+        velocity: Point,
+        acceleration: f64,
+        dummy: u8,
+    }
+
+    #[test]
+    fn test_arena() {
+        let arena = Arena::new(1024);
+
+        let mut p1: ArenaSlice<Point> = arena.allocate::<Point>(1).unwrap();
+        let mut p2: ArenaSlice<Point> = arena.allocate::<Point>(1).unwrap();
+
+        p1[0] = Point { x: 1.0, y: 2.0 };
+        p2[0] = Point { x: 3.0, y: 4.0 };
+
+        assert_eq!(p1[0].x, 1.0);
+        assert_eq!(p1[0].y, 2.0);
+
+        assert_eq!(p2[0].x, 3.0);
+        assert_eq!(p2[0].y, 4.0);
+
+        assert_eq!(arena.occupied(), core::mem::size_of::<Point>() * 2);
+    }
+
+    #[test]
+    fn test_arena_x() {
+        let arena = Arena::new(1024);
+
+        let mut entities: ArenaSlice<Entity> = arena.allocate::<Entity>(10).unwrap();
+
+        for entity in entities.iter_mut() {
+            entity.position = Point { x: 1.0, y: 2.0 };
+            entity.velocity = Point { x: 3.0, y: 4.0 };
+            entity.acceleration = 5.0;
+            entity.dummy = 0;
+        }
+
+        for entity in entities.iter() {
+            assert_eq!(entity.position.x, 1.0);
+            assert_eq!(entity.position.y, 2.0);
+
+            assert_eq!(entity.velocity.x, 3.0);
+            assert_eq!(entity.velocity.y, 4.0);
+
+            assert_eq!(entity.acceleration, 5.0);
+            assert_eq!(entity.dummy, 0);
+        }
+
+        assert_eq!(arena.occupied(), core::mem::size_of::<Entity>() * 10);
+    }
+
+    #[test]
+    fn test_full_arena() {
+        let arena = Arena::new(core::mem::size_of::<Point>() * 2);
+
+        let _p1: ArenaSlice<Point> = arena.allocate::<Point>(1).unwrap();
+        let _p2: ArenaSlice<Point> = arena.allocate::<Point>(1).unwrap();
+
+        assert!(arena.is_full());
+        arena.clear();
+        assert_eq!(arena.occupied(), 0);
+
+        let _p4: ArenaSlice<Point> = arena.allocate::<Point>(1).unwrap();
+        let _p5: ArenaSlice<Point> = arena.allocate::<Point>(1).unwrap();
+        assert!(arena.is_full());
+    }
+
+    #[test]
+    fn test_arena_clear() {
+        let arena = Arena::new(1024);
+
+        let mut p1 = arena.allocate::<Point>(1).unwrap();
+        let mut p2 = arena.allocate::<Point>(1).unwrap();
+
+        p1[0] = Point { x: 1.0, y: 2.0 };
+        p2[0] = Point { x: 3.0, y: 4.0 };
+
+        assert_eq!(p1[0].x, 1.0);
+        assert_eq!(p1[0].y, 2.0);
+
+        assert_eq!(p2[0].x, 3.0);
+        assert_eq!(p2[0].y, 4.0);
+
+        assert_eq!(arena.occupied(), core::mem::size_of::<Point>() * 2);
+
+        arena.clear();
+        assert_eq!(arena.occupied(), 0);
+
+        let mut p3 = arena.allocate::<Point>(1).unwrap();
+        let mut p4 = arena.allocate::<Point>(1).unwrap();
+
+        p3[0] = Point { x: 5.0, y: 6.0 };
+        p4[0] = Point { x: 7.0, y: 8.0 };
+
+        assert_eq!(p3[0].x, 5.0);
+        assert_eq!(p3[0].y, 6.0);
+
+        assert_eq!(p4[0].x, 7.0);
+        assert_eq!(p4[0].y, 8.0);
+
+        assert_eq!(arena.occupied(), core::mem::size_of::<Point>() * 2);
+    }
+
+    #[test]
+    fn test_arena_reset_to() {
+        let arena = Arena::new(1024);
+
+        let _p1 = arena.push(Point { x: 1.0, y: 2.0 }).unwrap();
+        let mark = arena.mark();
+        assert_eq!(mark.offset(), core::mem::size_of::<Point>());
+
+        let _p2 = arena.allocate::<Point>(4).unwrap();
+        assert_eq!(arena.occupied(), core::mem::size_of::<Point>() * 5);
+
+        arena.reset_to(mark);
+        assert_eq!(arena.occupied(), core::mem::size_of::<Point>());
+
+        arena.clear();
+        arena.reset_to(mark);
+        assert_eq!(arena.occupied(), 0);
+    }
+
+    #[test]
+    fn test_arena_scratch() {
+        let arena = Arena::new(1024);
+        let p1 = arena.push(Point { x: 1.0, y: 2.0 }).unwrap();
+
+        {
+            let scratch = arena.scratch();
+            let mut temp = scratch.allocate::<Point>(8).unwrap();
+            temp[0] = Point { x: 3.0, y: 4.0 };
+
+            {
+                let inner = scratch.scratch();
+                let _nested = inner.allocate::<u8>(100).unwrap();
+                assert_eq!(inner.mark().offset(), core::mem::size_of::<Point>() * 9);
+            }
+
+            assert_eq!(scratch.occupied(), core::mem::size_of::<Point>() * 9);
+        }
+
+        assert_eq!(arena.occupied(), core::mem::size_of::<Point>());
+        assert_eq!(p1[0].x, 1.0);
+    }
+
+    #[test]
+    fn test_growable_arena() {
+        let arena = Arena::growable(core::mem::size_of::<Point>() * 2);
+
+        let mut p1 = arena.push(Point { x: 1.0, y: 2.0 }).unwrap();
+        let _p2 = arena.push(Point { x: 3.0, y: 4.0 }).unwrap();
+        assert_eq!(arena.blocks(), 1);
+
+        let _p3 = arena.push(1u8).unwrap();
+        let p4 = arena.push(Point { x: 5.0, y: 6.0 }).unwrap();
+        assert_eq!(arena.blocks(), 2);
+
+        let big = arena.allocate::<Point>(16).unwrap();
+        assert_eq!(big.len(), 16);
+        assert_eq!(arena.blocks(), 3);
+        assert!(arena.wasted() > 0);
+
+        p1[0].x = 10.0;
+        assert_eq!(p1[0].x, 10.0);
+        assert_eq!(p4[0].y, 6.0);
+
+        arena.clear();
+        assert_eq!(arena.occupied(), 0);
+        assert_eq!(arena.wasted(), 0);
+        assert_eq!(arena.blocks(), 3);
+
+        let _p5 = arena.allocate::<Point>(3).unwrap();
+        assert_eq!(arena.blocks(), 3);
+    }
+
+    #[test]
+    fn test_growable_arena_reset_to() {
+        let arena = Arena::growable(64);
+
+        let _a = arena.allocate::<u8>(48).unwrap();
+        let mark = arena.mark();
+        let _b = arena.allocate::<u8>(32).unwrap();
+        let _c = arena.allocate::<u8>(32).unwrap();
+        assert_eq!(arena.mark().block(), 1);
+
+        arena.reset_to(mark);
+        assert_eq!(arena.mark(), mark);
+        assert_eq!(arena.occupied(), 48);
+
+        let _d = arena.allocate::<u8>(64).unwrap();
+        assert_eq!(arena.blocks(), 2);
+        assert_eq!(arena.wasted(), 16);
+    }
+
+    #[test]
+    fn test_arena_generation() {
+        let arena = Arena::new(1024);
+        let p1 = arena.push(Point { x: 1.0, y: 2.0 }).unwrap();
+        assert_eq!(arena.generation(), 0);
+        assert!(p1.is_live());
+
+        let _temp = {
+            let scratch = arena.scratch();
+            scratch.push(Point { x: 5.0, y: 6.0 }).unwrap()
+        };
+        assert!(p1.is_live());
+        #[cfg(debug_assertions)]
+        assert!(!_temp.is_live());
+
+        arena.clear();
+        assert_eq!(arena.generation(), 1);
+
+        let p2 = arena.push(Point { x: 3.0, y: 4.0 }).unwrap();
+        assert!(p2.is_live());
+        assert_eq!(p2[0].x, 3.0);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "ArenaSlice used after its arena was cleared")]
+    fn test_arena_use_after_clear() {
+        let arena = Arena::new(1024);
+        let p = arena.push(Point { x: 1.0, y: 2.0 }).unwrap();
+
+        arena.clear();
+        let _ = p[0].x;
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "ArenaSlice used after its arena was reset below it")]
+    fn test_arena_use_after_reset() {
+        let arena = Arena::growable(64);
+        let _kept = arena.push(1u64).unwrap();
+        let mark = arena.mark();
+        let p = arena.push(Point { x: 1.0, y: 2.0 }).unwrap();
+        let _other = arena.allocate::<u8>(100).unwrap();
+
+        arena.reset_to(mark);
+        let _reused = arena.allocate::<u8>(200).unwrap();
+        assert!(_kept.is_live());
+        let _ = p[0].x;
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "ArenaSlice used after its arena was reset below it")]
+    fn test_arena_use_after_later_reset() {
+        let arena = Arena::growable(64);
+        let mark = arena.mark();
+        let p = arena.push(Point { x: 1.0, y: 2.0 }).unwrap();
+
+        // Found live after a rewind above it, which must not hide the next one.
+        arena.scratch().push(1u64).unwrap();
+        assert!(p.is_live());
+
+        arena.reset_to(mark);
+        let _ = p[0].x;
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "ArenaSlice used after its arena was dropped")]
+    fn test_arena_use_after_drop() {
+        let arena = Arena::new(1024);
+        let s = arena.push_string("dangling").unwrap();
+
+        drop(arena);
+        let _ = s.len();
+    }
+
+    #[test]
+    fn test_arena_drop_order() {
+        struct Tracked<'a> {
+            id: usize,
+            log: &'a RefCell<Vec<usize>>,
+        }
+
+        impl Clone for Tracked<'_> {
+            fn clone(&self) -> Self {
+                Tracked {
+                    id: self.id + 10,
+                    log: self.log,
+                }
+            }
+        }
+
+        impl Drop for Tracked<'_> {
+            fn drop(&mut self) {
+                self.log.borrow_mut().push(self.id);
+            }
+        }
+
+        let log = RefCell::new(Vec::new());
+
+        {
+            let arena = Arena::growable(64);
+            let _a = arena.push(Tracked { id: 1, log: &log }).unwrap();
+            let mark = arena.mark();
+            let _b = arena.push(Tracked { id: 2, log: &log }).unwrap();
+
+            let source = [Tracked { id: 3, log: &log }, Tracked { id: 4, log: &log }];
+            let _c = arena.push_slice(&source).unwrap();
+            drop(source);
+            assert_eq!(*log.borrow(), vec![3, 4]);
+
+            arena.reset_to(mark);
+            assert_eq!(*log.borrow(), vec![3, 4, 13, 14, 2]);
+
+            let _d = arena.push(Tracked { id: 5, log: &log }).unwrap();
+            arena.clear();
+            assert_eq!(*log.borrow(), vec![3, 4, 13, 14, 2, 5, 1]);
+
+            let _e = arena.push(Tracked { id: 6, log: &log }).unwrap();
+        }
+
+        assert_eq!(*log.borrow(), vec![3, 4, 13, 14, 2, 5, 1, 6]);
+    }
+
+    #[test]
+    fn test_arena_copy_has_no_drop_records() {
+        let arena = Arena::new(1024);
+        let _p = arena.push(Point { x: 1.0, y: 2.0 }).unwrap();
+        let _s = arena.push_slice(&[1u32, 2, 3]).unwrap();
+
+        assert_eq!(
+            arena.occupied(),
+            core::mem::size_of::<Point>() + core::mem::size_of::<u32>() * 3
+        );
+    }
+
+    #[test]
+    fn test_arena_drops_vec() {
+        let arena = Arena::new(1024);
+        let values = arena.push(vec![1, 2, 3]).unwrap();
+        let strings = arena
+            .push_slice(&[String::from("a"), String::from("b")])
+            .unwrap();
+
+        assert_eq!(values[0], [1, 2, 3]);
+        assert_eq!(strings[1], "b");
+    }
+
+    #[test]
+    fn test_arena_string() {
+        let arena = Arena::new(1024);
+        let mut string = arena.allocate_string(8).unwrap();
+
+        assert_eq!(string.capacity(), 8);
+        assert_eq!(string, "");
+
+        string.push_str("mono").unwrap();
+        string.push('l').unwrap();
+        assert_eq!(string, "monol");
+        assert_eq!(string.remaining(), 3);
+        assert_eq!(string.push_str("itho"), None);
+        assert_eq!(string, "monol");
+
+        assert!(write!(&mut string, "{}", 123).is_ok());
+        assert_eq!(string, "monol123");
+        assert!(write!(&mut string, "!").is_err());
+
+        assert_eq!(string.pop(), Some('3'));
+        string.clear();
+        assert!(string.is_empty());
+        assert_eq!(string.capacity(), 8);
+
+        let pushed = arena.push_string("hello").unwrap();
+        assert_eq!(pushed.as_str(), "hello");
+        assert_eq!(pushed.remaining(), 0);
+    }
+
+    #[test]
+    fn test_arena_string_truncate() {
+        let arena = Arena::new(1024);
+        let mut string = arena.push_string("añb€").unwrap();
+
+        string.truncate(10);
+        assert_eq!(string, "añb€");
+
+        string.truncate(5);
+        assert_eq!(string, "añb");
+
+        string.truncate(2);
+        assert_eq!(string, "a");
+
+        string.truncate(0);
+        assert_eq!(string, "");
+    }
+
+    #[test]
+    fn test_arena_format() {
+        let arena = Arena::new(1024);
+        let path = "assets";
+        let name = "wall.png";
+
+        let string = arena_format!(&arena, "{}/{}", path, name).unwrap();
+        assert_eq!(string, "assets/wall.png");
+        assert_eq!(string.capacity(), string.len());
+        assert_eq!(arena.occupied(), "assets/wall.png".len());
+
+        let number = arena_format!(&arena, "{:>5}|{:.2}", 42, 1.5f32).unwrap();
+        assert_eq!(number, "   42|1.50");
+
+        let small = Arena::new(4);
+        assert!(arena_format!(&small, "{}", "too long").is_err());
+    }
+
+    #[test]
+    fn test_arena_alloc_error() {
+        let arena = Arena::new(64);
+        arena.allocate::<u8>(10).unwrap();
+
+        let error = arena.allocate::<u64>(8).unwrap_err();
+        assert_eq!(error.requested, 64);
+        assert_eq!(error.align, 8);
+        assert_eq!(error.remaining, 54);
+        assert_eq!(
+            error.to_string(),
+            "arena out of memory: requested 64 bytes (align 8) with 54 bytes remaining"
+        );
+
+        let error = arena.allocate::<u64>(usize::MAX / 4).unwrap_err();
+        assert_eq!(error.requested, usize::MAX);
+        assert_eq!(arena.occupied(), 10);
+
+        let growable = Arena::growable(64);
+        assert!(growable.allocate::<u8>(usize::MAX - 4).is_err());
+        assert_eq!(growable.blocks(), 1);
+    }
+
+    #[test]
+    fn test_arena_zero_sized() {
+        let arena = Arena::new(0);
+
+        let units = arena.allocate::<()>(100).unwrap();
+        assert_eq!(units.len(), 100);
+        assert!(arena.push(()).is_ok());
+        assert_eq!(arena.push_string("").unwrap(), "");
+        assert_eq!(arena.occupied(), 0);
+    }
+
+    #[test]
+    fn test_reserved_arena() {
+        let mut arena = Arena::reserved(1 << 30).unwrap();
+        arena.set_release_on_clear(true);
+        assert_eq!(arena.size(), 1 << 30);
+        assert_eq!(arena.committed(), 0);
+
+        let mut first = arena.allocate::<u8>(100).unwrap();
+        first.fill(7);
+        let committed = arena.committed();
+        assert!((100..1 << 20).contains(&committed));
+
+        let large = arena.allocate::<u8>(1 << 20).unwrap();
+        assert!(arena.committed() > 1 << 20);
+        assert_eq!(large.len(), 1 << 20);
+        assert!(first.iter().all(|byte| *byte == 7));
+
+        let ptr = first.as_ptr();
+        arena.clear();
+        assert_eq!(arena.occupied(), 0);
+
+        let second = arena.allocate::<u8>(100).unwrap();
+        assert_eq!(second.as_ptr(), ptr);
+        assert!(second.iter().all(|byte| *byte == 0));
+
+        assert!(arena.allocate::<u8>(1 << 31).is_err());
+    }
+
+    #[test]
+    fn test_arena_handle_mut() {
+        let arena = Arena::new(1024);
+
+        let mut p = arena.allocate::<Point>(1).unwrap();
+
+        for point in p.iter_mut() {
+            point.x = 1.0;
+            point.y = 2.0;
+        }
+
+        for point in p.iter() {
+            assert_eq!(point.x, 1.0);
+            assert_eq!(point.y, 2.0);
+        }
+    }
+}
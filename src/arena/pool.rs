@@ -0,0 +1,313 @@
+use super::{AllocError, Arena, ArenaSlice};
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+
+const NONE: u32 = u32::MAX;
+
+enum Entry<T> {
+    Occupied(T),
+    Free { next: u32 },
+}
+
+struct Slot<T> {
+    generation: u32,
+    entry: Entry<T>,
+}
+
+pub struct Handle<T> {
+    index: u32,
+    generation: u32,
+    marker: PhantomData<fn() -> T>,
+}
+
+// Live objects are dropped by the arena when it releases the slots, so a pool
+// outliving a clear never touches freed memory on drop.
+pub struct Pool<T> {
+    slots: ArenaSlice<Slot<T>>,
+    free: u32,
+    len: usize,
+}
+
+impl<T> Handle<T> {
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index && self.generation == other.generation
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> Hash for Handle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+        self.generation.hash(state);
+    }
+}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Handle({}v{})", self.index, self.generation)
+    }
+}
+
+impl<T> Pool<T> {
+    pub fn new(arena: &Arena, capacity: usize) -> Result<Self, AllocError> {
+        if capacity >= NONE as usize {
+            return Err(arena.alloc_error(usize::MAX, core::mem::align_of::<Slot<T>>()));
+        }
+
+        let mark = arena.mark();
+        let mut slots = arena.allocate::<Slot<T>>(capacity)?;
+
+        for i in 0..capacity {
+            let next = if i + 1 < capacity { i as u32 + 1 } else { NONE };
+
+            unsafe {
+                slots.as_mut_ptr().add(i).write(Slot {
+                    generation: 0,
+                    entry: Entry::Free { next },
+                });
+            }
+        }
+
+        if core::mem::needs_drop::<T>() {
+            arena.register_drop(mark, slots.as_mut_ptr(), capacity)?;
+        }
+
+        Ok(Pool {
+            slots,
+            free: if capacity > 0 { 0 } else { NONE },
+            len: 0,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.free == NONE
+    }
+
+    pub fn insert(&mut self, value: T) -> Option<Handle<T>> {
+        if self.free == NONE {
+            return None;
+        }
+
+        let index = self.free;
+        let slot = &mut self.slots[index as usize];
+
+        if let Entry::Free { next } = slot.entry {
+            self.free = next;
+        }
+
+        slot.entry = Entry::Occupied(value);
+        self.len += 1;
+
+        Some(Handle {
+            index,
+            generation: slot.generation,
+            marker: PhantomData,
+        })
+    }
+
+    pub fn remove(&mut self, handle: Handle<T>) -> Option<T> {
+        self.get(handle)?;
+
+        let slot = &mut self.slots[handle.index as usize];
+        let entry = core::mem::replace(&mut slot.entry, Entry::Free { next: self.free });
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free = handle.index;
+        self.len -= 1;
+
+        match entry {
+            Entry::Occupied(value) => Some(value),
+            Entry::Free { .. } => None,
+        }
+    }
+
+    pub fn get(&self, handle: Handle<T>) -> Option<&T> {
+        match self.slots.get(handle.index as usize)? {
+            Slot {
+                generation,
+                entry: Entry::Occupied(value),
+            } if *generation == handle.generation => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, handle: Handle<T>) -> Option<&mut T> {
+        match self.slots.get_mut(handle.index as usize)? {
+            Slot {
+                generation,
+                entry: Entry::Occupied(value),
+            } if *generation == handle.generation => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn contains(&self, handle: Handle<T>) -> bool {
+        self.get(handle).is_some()
+    }
+
+    pub fn clear(&mut self) {
+        for index in 0..self.slots.len() {
+            let slot = &mut self.slots[index];
+
+            if let Entry::Occupied(_) = slot.entry {
+                slot.entry = Entry::Free { next: self.free };
+                slot.generation = slot.generation.wrapping_add(1);
+                self.free = index as u32;
+            }
+        }
+
+        self.len = 0;
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Handle<T>, &T)> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(index, slot)| match &slot.entry {
+                Entry::Occupied(value) => Some((
+                    Handle {
+                        index: index as u32,
+                        generation: slot.generation,
+                        marker: PhantomData,
+                    },
+                    value,
+                )),
+                Entry::Free { .. } => None,
+            })
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Handle<T>, &mut T)> {
+        self.slots
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, slot)| match &mut slot.entry {
+                Entry::Occupied(value) => Some((
+                    Handle {
+                        index: index as u32,
+                        generation: slot.generation,
+                        marker: PhantomData,
+                    },
+                    value,
+                )),
+                Entry::Free { .. } => None,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::rc::Rc;
+
+    #[test]
+    fn test_pool() {
+        let arena = Arena::new(4096);
+        let mut pool = Pool::new(&arena, 3).unwrap();
+
+        let a = pool.insert("a").unwrap();
+        let b = pool.insert("b").unwrap();
+        let c = pool.insert("c").unwrap();
+        assert!(pool.is_full());
+        assert_eq!(pool.insert("d"), None);
+
+        assert_eq!(pool.get(b), Some(&"b"));
+        assert_eq!(pool.remove(b), Some("b"));
+        assert_eq!(pool.get(b), None);
+        assert_eq!(pool.remove(b), None);
+        assert_eq!(pool.len(), 2);
+
+        let d = pool.insert("d").unwrap();
+        assert_eq!(d.index(), b.index());
+        assert_ne!(d, b);
+        assert_eq!(pool.get(b), None);
+        assert_eq!(pool.get(d), Some(&"d"));
+
+        *pool.get_mut(a).unwrap() = "A";
+        let live: alloc::vec::Vec<_> = pool.iter().map(|(_, value)| *value).collect();
+        assert_eq!(live, ["A", "d", "c"]);
+        assert!(pool.iter().all(|(handle, _)| pool.contains(handle)));
+        assert!(pool.contains(c));
+    }
+
+    #[test]
+    fn test_pool_drops() {
+        let value = Rc::new(());
+        let arena = Arena::new(4096);
+
+        {
+            let mut pool = Pool::new(&arena, 4).unwrap();
+            let first = pool.insert(value.clone()).unwrap();
+            pool.insert(value.clone()).unwrap();
+            pool.insert(value.clone()).unwrap();
+            assert_eq!(Rc::strong_count(&value), 4);
+
+            drop(pool.remove(first));
+            assert_eq!(Rc::strong_count(&value), 3);
+
+            for (_, value) in pool.iter_mut() {
+                *value = Rc::new(());
+            }
+            assert_eq!(Rc::strong_count(&value), 1);
+
+            pool.insert(value.clone()).unwrap();
+            pool.clear();
+            assert!(pool.is_empty());
+            assert_eq!(Rc::strong_count(&value), 1);
+
+            pool.insert(value.clone()).unwrap();
+        }
+
+        assert_eq!(Rc::strong_count(&value), 2);
+        arena.clear();
+        assert_eq!(Rc::strong_count(&value), 1);
+    }
+
+    #[test]
+    fn test_pool_outlives_clear() {
+        let value = Rc::new(());
+        let arena = Arena::new(4096);
+
+        {
+            let mut pool = Pool::new(&arena, 4).unwrap();
+            pool.insert(value.clone()).unwrap();
+            pool.insert(value.clone()).unwrap();
+
+            arena.clear();
+            assert_eq!(Rc::strong_count(&value), 1);
+            arena.push_slice(&[0xffu8; 4096]).unwrap();
+        }
+
+        assert_eq!(Rc::strong_count(&value), 1);
+    }
+}
@@ -0,0 +1,443 @@
+use super::{Arena, ArenaSlice, Memory};
+use core::ffi::c_void;
+use core::fmt;
+use core::marker::PhantomData;
+use libc::{fstat, mmap, munmap, stat, write, MAP_FAILED, MAP_PRIVATE, PROT_READ, PROT_WRITE};
+
+const MAGIC: [u8; 8] = *b"MONOARNA";
+const VERSION: u32 = 1;
+const MAX_ALIGNMENT: usize = 4096;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Header {
+    magic: [u8; 8],
+    version: u32,
+    alignment: u32,
+    size: u64,
+    data: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotError {
+    Io(i32),
+    MultipleBlocks,
+    Destructors,
+    InvalidMagic,
+    UnsupportedVersion(u32),
+    InvalidAlignment(u32),
+    Truncated { expected: u64, actual: u64 },
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io(errno) => write!(f, "snapshot i/o failed with errno {}", errno),
+            SnapshotError::MultipleBlocks => {
+                write!(f, "arena spans more than one block and cannot be saved")
+            }
+            SnapshotError::Destructors => {
+                write!(f, "arena holds values with destructors and cannot be saved")
+            }
+            SnapshotError::InvalidMagic => write!(f, "not an arena snapshot"),
+            SnapshotError::UnsupportedVersion(version) => {
+                write!(f, "unsupported snapshot version {}", version)
+            }
+            SnapshotError::InvalidAlignment(alignment) => {
+                write!(f, "invalid snapshot alignment {}", alignment)
+            }
+            SnapshotError::Truncated { expected, actual } => write!(
+                f,
+                "snapshot truncated: expected {} bytes, found {}",
+                expected, actual
+            ),
+        }
+    }
+}
+
+// A private, copy-on-write mapping of a snapshot file. The arena data starts
+// `data` bytes into the mapping.
+pub(super) struct Mapping {
+    ptr: *mut u8,
+    len: usize,
+    data: usize,
+}
+
+impl Mapping {
+    pub(super) fn as_ptr(&self) -> *mut u8 {
+        unsafe { self.ptr.add(self.data) }
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.len - self.data
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        unsafe { munmap(self.ptr as *mut c_void, self.len) };
+    }
+}
+
+/// Types that can be read back from snapshot bytes: every bit pattern is a valid
+/// value and there are no pointers or padding.
+///
+/// # Safety
+///
+/// The implementing type must be `#[repr(C)]` or `#[repr(transparent)]` over
+/// fields that are themselves `Pod`, with no padding between them.
+pub unsafe trait Pod: Copy {}
+
+macro_rules! impl_pod {
+    ($($ty:ty),*) => {
+        $(unsafe impl Pod for $ty {})*
+    };
+}
+
+impl_pod!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+unsafe impl<T: Pod, const N: usize> Pod for [T; N] {}
+unsafe impl<T> Pod for RelPtr<T> {}
+unsafe impl<T> Pod for RelSlice<T> {}
+
+#[repr(C)]
+pub struct RelPtr<T> {
+    offset: u64,
+    marker: PhantomData<*const T>,
+}
+
+#[repr(C)]
+pub struct RelSlice<T> {
+    offset: u64,
+    len: u64,
+    marker: PhantomData<*const T>,
+}
+
+impl<T> Clone for RelPtr<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for RelPtr<T> {}
+
+impl<T> Clone for RelSlice<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for RelSlice<T> {}
+
+impl<T> fmt::Debug for RelPtr<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RelPtr(+{})", self.offset)
+    }
+}
+
+impl<T> fmt::Debug for RelSlice<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RelSlice(+{}, {})", self.offset, self.len)
+    }
+}
+
+impl<T: Pod> RelPtr<T> {
+    pub fn from_offset(offset: usize) -> Self {
+        RelPtr {
+            offset: offset as u64,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset as usize
+    }
+
+    pub fn get<'a>(&self, arena: &'a Arena) -> Option<&'a T> {
+        let ptr = arena.resolve::<T>(self.offset, 1)?;
+        Some(unsafe { &*ptr })
+    }
+}
+
+impl<T: Pod> RelSlice<T> {
+    pub fn offset(&self) -> usize {
+        self.offset as usize
+    }
+
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get<'a>(&self, arena: &'a Arena) -> Option<&'a [T]> {
+        let ptr = arena.resolve::<T>(self.offset, self.len)?;
+        Some(unsafe { core::slice::from_raw_parts(ptr, self.len as usize) })
+    }
+}
+
+fn errno() -> i32 {
+    std::io::Error::last_os_error().raw_os_error().unwrap_or(0)
+}
+
+fn write_all(fd: i32, mut bytes: &[u8]) -> Result<(), SnapshotError> {
+    while !bytes.is_empty() {
+        let written = unsafe { write(fd, bytes.as_ptr() as *const c_void, bytes.len()) };
+
+        if written < 0 {
+            let errno = errno();
+            if errno == libc::EINTR {
+                continue;
+            }
+            return Err(SnapshotError::Io(errno));
+        }
+
+        bytes = &bytes[written as usize..];
+    }
+
+    Ok(())
+}
+
+impl Arena {
+    // Offsets are relative to the first block, the only one a snapshot keeps.
+    fn offset_of(&self, ptr: *const u8, size: usize) -> Option<u64> {
+        let base = self.data.as_ptr() as usize;
+        let start = (ptr as usize).checked_sub(base)?;
+
+        if start.checked_add(size)? > self.offset.get() {
+            return None;
+        }
+
+        Some(start as u64)
+    }
+
+    fn resolve<T>(&self, offset: u64, len: u64) -> Option<*const T> {
+        let size = (len as usize).checked_mul(core::mem::size_of::<T>())?;
+        let end = (offset as usize).checked_add(size)?;
+
+        if end > self.offset.get() {
+            return None;
+        }
+
+        let ptr = unsafe { self.data.as_ptr().add(offset as usize) } as *const T;
+
+        if !(ptr as usize).is_multiple_of(core::mem::align_of::<T>()) {
+            return None;
+        }
+
+        Some(ptr)
+    }
+
+    pub fn rel_ptr<T>(&self, value: &T) -> Option<RelPtr<T>> {
+        let offset = self.offset_of(value as *const T as *const u8, core::mem::size_of::<T>())?;
+
+        Some(RelPtr {
+            offset,
+            marker: PhantomData,
+        })
+    }
+
+    pub fn rel_slice<T>(&self, slice: &ArenaSlice<T>) -> Option<RelSlice<T>> {
+        let offset = self.offset_of(
+            slice.as_ptr() as *const u8,
+            core::mem::size_of_val(&slice[..]),
+        )?;
+
+        Some(RelSlice {
+            offset,
+            len: slice.len() as u64,
+            marker: PhantomData,
+        })
+    }
+
+    pub fn save_to(&self, fd: i32) -> Result<(), SnapshotError> {
+        if self.blocks() > 1 {
+            return Err(SnapshotError::MultipleBlocks);
+        }
+
+        if !self.drops.get().is_null() {
+            return Err(SnapshotError::Destructors);
+        }
+
+        // Data is aligned relative to the address of the first block, so the
+        // loaded copy has to start on an address with at least the same alignment.
+        let base = self.data.as_ptr() as usize;
+        let alignment = (1usize << base.trailing_zeros().min(31)).min(MAX_ALIGNMENT);
+        let data = core::mem::size_of::<Header>().next_multiple_of(alignment);
+
+        let header = Header {
+            magic: MAGIC,
+            version: VERSION,
+            alignment: alignment as u32,
+            size: self.offset.get() as u64,
+            data: data as u64,
+        };
+
+        let mut prefix = alloc::vec![0u8; data];
+        unsafe {
+            core::ptr::write_unaligned(prefix.as_mut_ptr() as *mut Header, header);
+        }
+
+        write_all(fd, &prefix)?;
+        write_all(fd, unsafe {
+            core::slice::from_raw_parts(self.data.as_ptr(), self.offset.get())
+        })
+    }
+
+    pub fn load_from(fd: i32) -> Result<Arena, SnapshotError> {
+        let mut data: stat = unsafe { core::mem::zeroed() };
+
+        if unsafe { fstat(fd, &mut data) } < 0 {
+            return Err(SnapshotError::Io(errno()));
+        }
+
+        let len = data.st_size as u64;
+        let header_size = core::mem::size_of::<Header>() as u64;
+
+        if len < header_size {
+            return Err(SnapshotError::Truncated {
+                expected: header_size,
+                actual: len,
+            });
+        }
+
+        let ptr = unsafe {
+            mmap(
+                core::ptr::null_mut(),
+                len as usize,
+                PROT_READ | PROT_WRITE,
+                MAP_PRIVATE,
+                fd,
+                0,
+            )
+        };
+
+        if ptr == MAP_FAILED {
+            return Err(SnapshotError::Io(errno()));
+        }
+
+        let header = unsafe { core::ptr::read_unaligned(ptr as *const Header) };
+        let mapping = Mapping {
+            ptr: ptr as *mut u8,
+            len: len as usize,
+            data: header.data as usize,
+        };
+
+        if header.magic != MAGIC {
+            return Err(SnapshotError::InvalidMagic);
+        }
+
+        if header.version != VERSION {
+            return Err(SnapshotError::UnsupportedVersion(header.version));
+        }
+
+        let alignment = header.alignment as usize;
+
+        if !alignment.is_power_of_two()
+            || alignment > MAX_ALIGNMENT
+            || header.data < header_size
+            || !(header.data as usize).is_multiple_of(alignment)
+        {
+            return Err(SnapshotError::InvalidAlignment(header.alignment));
+        }
+
+        let expected = header.data.saturating_add(header.size);
+
+        if expected > len {
+            return Err(SnapshotError::Truncated {
+                expected,
+                actual: len,
+            });
+        }
+
+        let arena = Arena::with_memory(Memory::Mapped(mapping));
+        arena.offset.set(header.size as usize);
+        Ok(arena)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::fd::AsRawFd;
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct Baked {
+        name: RelSlice<u8>,
+        values: RelSlice<f32>,
+        count: RelPtr<u64>,
+    }
+
+    unsafe impl Pod for Baked {}
+
+    #[test]
+    fn test_arena_snapshot() {
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("monolith-snapshot-{}.bin", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        {
+            let arena = Arena::new(4096);
+            let mut root = arena.allocate::<Baked>(1).unwrap();
+            let name = arena.push_slice(b"cube").unwrap();
+            let values = arena.push_slice(&[1.0f32, 2.0, 3.0]).unwrap();
+            let count = arena.push(3u64).unwrap();
+
+            root[0] = Baked {
+                name: arena.rel_slice(&name).unwrap(),
+                values: arena.rel_slice(&values).unwrap(),
+                count: arena.rel_ptr(&count[0]).unwrap(),
+            };
+
+            let file = std::fs::File::create(path).unwrap();
+            arena.save_to(file.as_raw_fd()).unwrap();
+        }
+
+        let file = std::fs::File::open(path).unwrap();
+        let arena = Arena::load_from(file.as_raw_fd()).unwrap();
+        let baked = RelPtr::<Baked>::from_offset(0).get(&arena).unwrap();
+
+        assert_eq!(baked.name.get(&arena).unwrap(), b"cube");
+        assert_eq!(baked.values.get(&arena).unwrap(), &[1.0, 2.0, 3.0]);
+        assert_eq!(*baked.count.get(&arena).unwrap(), 3);
+        assert!(arena.allocate::<u8>(1).is_err());
+
+        drop(file);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_arena_snapshot_errors() {
+        let arena = Arena::growable(16);
+        arena.allocate::<u8>(32).unwrap();
+        assert_eq!(arena.save_to(-1), Err(SnapshotError::MultipleBlocks));
+
+        let arena = Arena::new(256);
+        arena.push(alloc::vec![1]).unwrap();
+        assert_eq!(arena.save_to(-1), Err(SnapshotError::Destructors));
+
+        let arena = Arena::new(64);
+        let other = Arena::new(64);
+        let value = other.push(1u32).unwrap();
+        assert!(arena.rel_ptr(&value[0]).is_none());
+
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("monolith-invalid-{}.bin", std::process::id()));
+        let path = path.to_str().unwrap();
+        std::fs::write(path, [0u8; 64]).unwrap();
+
+        let file = std::fs::File::open(path).unwrap();
+        let result = Arena::load_from(file.as_raw_fd());
+        assert_eq!(result.err(), Some(SnapshotError::InvalidMagic));
+        assert_eq!(
+            Arena::load_from(-1).err(),
+            Some(SnapshotError::Io(libc::EBADF))
+        );
+
+        drop(file);
+        std::fs::remove_file(path).unwrap();
+    }
+}
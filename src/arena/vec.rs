@@ -0,0 +1,476 @@
+use super::{AllocError, Arena, ArenaSlice, DropRecord};
+use core::ops::{Deref, DerefMut};
+use core::ptr::NonNull;
+
+// Elements are dropped by the arena through a record that follows the buffer
+// and length, so a vec outliving a clear never touches freed memory on drop.
+pub struct ArenaVec<'a, T> {
+    arena: &'a Arena,
+    ptr: *mut T,
+    len: usize,
+    capacity: usize,
+    record: *mut DropRecord,
+}
+
+impl<'a, T> ArenaVec<'a, T> {
+    pub fn new(arena: &'a Arena) -> Self {
+        let capacity = if core::mem::size_of::<T>() == 0 {
+            usize::MAX
+        } else {
+            0
+        };
+
+        ArenaVec {
+            arena,
+            ptr: NonNull::dangling().as_ptr(),
+            len: 0,
+            capacity,
+            record: core::ptr::null_mut(),
+        }
+    }
+
+    pub fn with_capacity(arena: &'a Arena, capacity: usize) -> Result<Self, AllocError> {
+        let mut vec = ArenaVec::new(arena);
+        vec.reserve(capacity)?;
+        Ok(vec)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn reserve(&mut self, additional: usize) -> Result<(), AllocError> {
+        // The destructor record goes right below the first buffer, so that a
+        // reset can't free the buffer without also reaching the record.
+        if core::mem::needs_drop::<T>() && self.record.is_null() && additional > 0 {
+            let mark = self.arena.mark();
+            self.record = self.arena.reserve_drop_record()?;
+            self.arena.link_drop(self.record, mark, self.ptr, 0);
+        }
+
+        let required = self.len.checked_add(additional).ok_or(
+            self.arena
+                .alloc_error(usize::MAX, core::mem::align_of::<T>()),
+        )?;
+
+        if required <= self.capacity {
+            return Ok(());
+        }
+
+        let capacity = required.max(self.capacity.saturating_mul(2)).max(4);
+        self.grow(capacity)
+    }
+
+    fn grow(&mut self, capacity: usize) -> Result<(), AllocError> {
+        let size = core::mem::size_of::<T>();
+        let align = core::mem::align_of::<T>();
+        let new_size = capacity
+            .checked_mul(size)
+            .ok_or(self.arena.alloc_error(usize::MAX, align))?;
+
+        if self.capacity > 0
+            && self
+                .arena
+                .grow_in_place(self.ptr as *mut u8, self.capacity * size, new_size)
+        {
+            self.capacity = capacity;
+            return Ok(());
+        }
+
+        // A relocated buffer gets a new record right below it, so that a reset
+        // can't free the buffer without also reaching the record.
+        let mark = self.arena.mark();
+        let record = if core::mem::needs_drop::<T>() && self.capacity > 0 {
+            self.arena.reserve_drop_record()?
+        } else {
+            self.record
+        };
+
+        let ptr = self.arena.reserve(new_size, align)? as *mut T;
+
+        unsafe {
+            ptr.copy_from_nonoverlapping(self.ptr, self.len);
+        }
+
+        // The old buffer's record stays linked but no longer drops anything.
+        if record != self.record {
+            unsafe { (*self.record).len = 0 };
+            self.arena.link_drop(record, mark, ptr, self.len);
+            self.record = record;
+        }
+
+        self.ptr = ptr;
+        self.capacity = capacity;
+        self.sync();
+        Ok(())
+    }
+
+    // Keeps the arena's record of the live elements in step with the vec.
+    fn sync(&self) {
+        if !self.record.is_null() {
+            unsafe {
+                (*self.record).ptr = self.ptr as *mut u8;
+                (*self.record).len = self.len;
+            }
+        }
+    }
+
+    pub fn push(&mut self, value: T) -> Result<(), AllocError> {
+        if self.len == self.capacity {
+            self.reserve(1)?;
+        }
+
+        unsafe {
+            self.ptr.add(self.len).write(value);
+        }
+
+        self.len += 1;
+        self.sync();
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+
+        self.len -= 1;
+        self.sync();
+        Some(unsafe { self.ptr.add(self.len).read() })
+    }
+
+    pub fn extend_from_slice(&mut self, values: &[T]) -> Result<(), AllocError>
+    where
+        T: Clone,
+    {
+        self.reserve(values.len())?;
+
+        for value in values {
+            unsafe {
+                self.ptr.add(self.len).write(value.clone());
+            }
+
+            self.len += 1;
+            self.sync();
+        }
+
+        Ok(())
+    }
+
+    pub fn insert(&mut self, index: usize, value: T) -> Result<(), AllocError> {
+        assert!(
+            index <= self.len,
+            "insertion index (is {}) should be <= len (is {})",
+            index,
+            self.len
+        );
+
+        if self.len == self.capacity {
+            self.reserve(1)?;
+        }
+
+        unsafe {
+            let slot = self.ptr.add(index);
+            slot.copy_to(slot.add(1), self.len - index);
+            slot.write(value);
+        }
+
+        self.len += 1;
+        self.sync();
+        Ok(())
+    }
+
+    pub fn remove(&mut self, index: usize) -> T {
+        assert!(
+            index < self.len,
+            "removal index (is {}) should be < len (is {})",
+            index,
+            self.len
+        );
+
+        self.len -= 1;
+
+        let value = unsafe {
+            let slot = self.ptr.add(index);
+            let value = slot.read();
+            slot.copy_from(slot.add(1), self.len - index);
+            value
+        };
+
+        self.sync();
+        value
+    }
+
+    pub fn retain<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let len = self.len;
+        let mut kept = 0;
+
+        // Elements are leaked rather than double dropped if the predicate panics.
+        self.len = 0;
+        self.sync();
+
+        for i in 0..len {
+            unsafe {
+                let slot = self.ptr.add(i);
+
+                if keep(&*slot) {
+                    if kept != i {
+                        slot.copy_to_nonoverlapping(self.ptr.add(kept), 1);
+                    }
+
+                    kept += 1;
+                } else {
+                    slot.drop_in_place();
+                }
+            }
+        }
+
+        self.len = kept;
+        self.sync();
+    }
+
+    pub fn truncate(&mut self, len: usize) {
+        if len >= self.len {
+            return;
+        }
+
+        let tail =
+            core::ptr::slice_from_raw_parts_mut(unsafe { self.ptr.add(len) }, self.len - len);
+        self.len = len;
+        self.sync();
+
+        unsafe {
+            tail.drop_in_place();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    pub fn into_slice(self) -> ArenaSlice<T> {
+        self.arena.slice(self.ptr, self.len)
+    }
+}
+
+impl<T> Deref for ArenaVec<'_, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { core::slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl<T> DerefMut for ArenaVec<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { core::slice::from_raw_parts_mut(self.ptr, self.len) }
+    }
+}
+
+impl<T> AsRef<[T]> for ArenaVec<'_, T> {
+    fn as_ref(&self) -> &[T] {
+        self.deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::rc::Rc;
+    use alloc::string::String;
+
+    #[test]
+    fn test_arena_vec_push() {
+        let arena = Arena::new(1024);
+        let mut vec = ArenaVec::new(&arena);
+
+        for i in 0..10u32 {
+            vec.push(i).unwrap();
+        }
+
+        assert_eq!(vec.len(), 10);
+        assert_eq!(&vec[..], &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(vec.pop(), Some(9));
+        assert_eq!(
+            arena.occupied(),
+            vec.capacity() * core::mem::size_of::<u32>()
+        );
+    }
+
+    #[test]
+    fn test_arena_vec_grows_in_place() {
+        let arena = Arena::new(1024);
+        let mut vec = ArenaVec::<u64>::with_capacity(&arena, 4).unwrap();
+        let ptr = vec.as_ptr();
+
+        vec.extend_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8, 9]).unwrap();
+
+        assert_eq!(vec.as_ptr(), ptr);
+        assert_eq!(
+            arena.occupied(),
+            vec.capacity() * core::mem::size_of::<u64>()
+        );
+    }
+
+    #[test]
+    fn test_arena_vec_relocates() {
+        let arena = Arena::new(1024);
+        let mut vec = ArenaVec::<u64>::with_capacity(&arena, 4).unwrap();
+        vec.extend_from_slice(&[1, 2, 3, 4]).unwrap();
+
+        let ptr = vec.as_ptr();
+        let _other = arena.push(0u8).unwrap();
+        vec.push(5).unwrap();
+
+        assert_ne!(vec.as_ptr(), ptr);
+        assert_eq!(&vec[..], &[1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_arena_vec_insert_remove_retain() {
+        let arena = Arena::new(1024);
+        let mut vec = ArenaVec::new(&arena);
+        vec.extend_from_slice(&[1, 2, 4, 5]).unwrap();
+
+        vec.insert(2, 3).unwrap();
+        vec.insert(0, 0).unwrap();
+        vec.insert(6, 6).unwrap();
+        assert_eq!(&vec[..], &[0, 1, 2, 3, 4, 5, 6]);
+
+        assert_eq!(vec.remove(0), 0);
+        assert_eq!(vec.remove(5), 6);
+        assert_eq!(&vec[..], &[1, 2, 3, 4, 5]);
+
+        vec.retain(|value| value % 2 == 1);
+        assert_eq!(&vec[..], &[1, 3, 5]);
+
+        let slice = vec.into_slice();
+        assert_eq!(&slice[..], &[1, 3, 5]);
+    }
+
+    #[test]
+    fn test_arena_vec_drops() {
+        let counter = Rc::new(());
+        let arena = Arena::new(1024);
+
+        {
+            let mut vec = ArenaVec::new(&arena);
+
+            for _ in 0..6 {
+                vec.push(counter.clone()).unwrap();
+            }
+
+            vec.retain(|_| false);
+            vec.push(counter.clone()).unwrap();
+            assert_eq!(Rc::strong_count(&counter), 2);
+        }
+
+        assert_eq!(Rc::strong_count(&counter), 2);
+
+        let mut vec = ArenaVec::new(&arena);
+        vec.push(String::from("hello")).unwrap();
+        vec.push(String::from("world")).unwrap();
+        vec.push(String::from("!")).unwrap();
+
+        let strings = vec.into_slice();
+        assert_eq!(strings[1], "world");
+
+        let mut vec = ArenaVec::new(&arena);
+        vec.push(counter.clone()).unwrap();
+        let _counters = vec.into_slice();
+        assert_eq!(Rc::strong_count(&counter), 3);
+
+        arena.clear();
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    fn test_arena_vec_outlives_clear() {
+        let counter = Rc::new(());
+        let arena = Arena::new(4096);
+
+        {
+            let mut vec = ArenaVec::new(&arena);
+            vec.push(String::from("hello")).unwrap();
+            vec.push(String::from("world")).unwrap();
+
+            arena.clear();
+            arena.push_slice(&[0x41u8; 512]).unwrap();
+        }
+
+        {
+            let mut vec = ArenaVec::new(&arena);
+            vec.push(counter.clone()).unwrap();
+            vec.push(counter.clone()).unwrap();
+            assert_eq!(Rc::strong_count(&counter), 3);
+
+            arena.clear();
+            assert_eq!(Rc::strong_count(&counter), 1);
+            arena.push_slice(&[0x41u8; 512]).unwrap();
+        }
+
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    fn test_arena_vec_into_slice_survives_later_resets() {
+        let counter = Rc::new(());
+        let arena = Arena::new(1024);
+
+        let mut vec = ArenaVec::new(&arena);
+        vec.push(counter.clone()).unwrap();
+        vec.push(counter.clone()).unwrap();
+
+        let mark = arena.mark();
+        let counters = vec.into_slice();
+        arena.reset_to(mark);
+        assert_eq!(Rc::strong_count(&counter), 3);
+        assert!(counters.is_live());
+
+        // A buffer relocated past a mark is released with it.
+        let mut vec = ArenaVec::with_capacity(&arena, 1).unwrap();
+        vec.push(counter.clone()).unwrap();
+        let _other = arena.push(0u8).unwrap();
+        let mark = arena.mark();
+        vec.extend_from_slice(&[
+            counter.clone(),
+            counter.clone(),
+            counter.clone(),
+            counter.clone(),
+        ])
+        .unwrap();
+        let _moved = vec.into_slice();
+        assert_eq!(Rc::strong_count(&counter), 8);
+
+        arena.reset_to(mark);
+        assert_eq!(Rc::strong_count(&counter), 3);
+
+        arena.clear();
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    fn test_arena_vec_growable_arena() {
+        let arena = Arena::growable(32);
+        let mut vec = ArenaVec::new(&arena);
+
+        for i in 0..100u32 {
+            vec.push(i).unwrap();
+        }
+
+        assert_eq!(vec.len(), 100);
+        assert_eq!(vec[99], 99);
+        assert!(arena.blocks() > 1);
+    }
+}
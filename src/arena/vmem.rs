@@ -0,0 +1,108 @@
+use core::cell::Cell;
+use core::ffi::c_void;
+use libc::{
+    madvise, mmap, mprotect, munmap, sysconf, _SC_PAGESIZE, MADV_DONTNEED, MAP_ANONYMOUS,
+    MAP_FAILED, MAP_NORESERVE, MAP_PRIVATE, PROT_NONE, PROT_READ, PROT_WRITE,
+};
+
+// Pages are committed in chunks of at least this size so a bump pointer moving
+// through small allocations doesn't issue an mprotect call per page.
+const COMMIT_SIZE: usize = 64 * 1024;
+
+pub(super) struct VirtualMemory {
+    ptr: *mut u8,
+    reserved: usize,
+    committed: Cell<usize>,
+    granularity: usize,
+    release_on_clear: bool,
+}
+
+impl VirtualMemory {
+    pub(super) fn reserve(size: usize) -> Option<VirtualMemory> {
+        let page_size = unsafe { sysconf(_SC_PAGESIZE) } as usize;
+        let reserved = size.max(1).checked_next_multiple_of(page_size)?;
+
+        let ptr = unsafe {
+            mmap(
+                core::ptr::null_mut(),
+                reserved,
+                PROT_NONE,
+                MAP_PRIVATE | MAP_ANONYMOUS | MAP_NORESERVE,
+                -1,
+                0,
+            )
+        };
+
+        if ptr == MAP_FAILED {
+            return None;
+        }
+
+        Some(VirtualMemory {
+            ptr: ptr as *mut u8,
+            reserved,
+            committed: Cell::new(0),
+            granularity: COMMIT_SIZE.next_multiple_of(page_size),
+            release_on_clear: false,
+        })
+    }
+
+    pub(super) fn as_ptr(&self) -> *mut u8 {
+        self.ptr
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.reserved
+    }
+
+    pub(super) fn committed(&self) -> usize {
+        self.committed.get()
+    }
+
+    pub(super) fn set_release_on_clear(&mut self, release: bool) {
+        self.release_on_clear = release;
+    }
+
+    pub(super) fn commit(&self, end: usize) -> bool {
+        let committed = self.committed.get();
+
+        if end <= committed {
+            return true;
+        }
+
+        if end > self.reserved {
+            return false;
+        }
+
+        let target = end.next_multiple_of(self.granularity).min(self.reserved);
+        let result = unsafe {
+            mprotect(
+                self.ptr.add(committed) as *mut c_void,
+                target - committed,
+                PROT_READ | PROT_WRITE,
+            )
+        };
+
+        if result != 0 {
+            return false;
+        }
+
+        self.committed.set(target);
+        true
+    }
+
+    // Hands the physical pages back to the kernel. They stay committed and read
+    // back as zeroes the next time they are touched.
+    pub(super) fn clear(&self) {
+        let committed = self.committed.get();
+
+        if self.release_on_clear && committed > 0 {
+            unsafe { madvise(self.ptr as *mut c_void, committed, MADV_DONTNEED) };
+        }
+    }
+}
+
+impl Drop for VirtualMemory {
+    fn drop(&mut self) {
+        unsafe { munmap(self.ptr as *mut c_void, self.reserved) };
+    }
+}
@@ -1,4 +1,4 @@
-use crate::arena::{Arena, ArenaSlice};
+use crate::arena::{AllocError, Arena, ArenaSlice, ArenaVec};
 use crate::math::*;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -24,6 +24,31 @@ pub enum Element {
     Quad(ArenaSlice<Vec4u>),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeshError {
+    Alloc(AllocError),
+    Empty,
+    MismatchedElements,
+}
+
+impl From<AllocError> for MeshError {
+    fn from(error: AllocError) -> Self {
+        MeshError::Alloc(error)
+    }
+}
+
+impl core::fmt::Display for MeshError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            MeshError::Alloc(error) => write!(f, "{}", error),
+            MeshError::Empty => write!(f, "mesh has no vertices or elements"),
+            MeshError::MismatchedElements => write!(f, "mesh element types do not match"),
+        }
+    }
+}
+
+impl std::error::Error for MeshError {}
+
 pub struct Mesh {
     elements: Element,
     vertices: VertexData,
@@ -103,9 +128,13 @@ impl Mesh {
     }
 }
 
-pub fn merge_meshes(arena: &Arena, meshes: &[Mesh], element_type: ElementType) -> Option<Mesh> {
+pub fn merge_meshes(
+    arena: &Arena,
+    meshes: &[Mesh],
+    element_type: ElementType,
+) -> Result<Mesh, MeshError> {
     if meshes.is_empty() {
-        return None;
+        return Err(MeshError::Empty);
     }
 
     if meshes.iter().any(|m| match element_type {
@@ -114,7 +143,7 @@ pub fn merge_meshes(arena: &Arena, meshes: &[Mesh], element_type: ElementType) -
         ElementType::Triangle => !matches!(m.elements(), Element::Triangle(_)),
         ElementType::Quad => !matches!(m.elements(), Element::Quad(_)),
     }) {
-        return None;
+        return Err(MeshError::MismatchedElements);
     }
 
     let element_count = meshes
@@ -142,19 +171,54 @@ pub fn merge_meshes(arena: &Arena, meshes: &[Mesh], element_type: ElementType) -
     let vertex_count = meshes.iter().map(|m| m.len()).sum();
 
     if element_count == 0 || vertex_count == 0 {
-        return None;
+        return Err(MeshError::Empty);
     }
 
-    let mut elements = match element_type {
+    let mut merged = allocate_mesh(arena, &element_type, vertex_count, element_count)?;
+    copy_meshes(meshes, &element_type, &mut merged)?;
+
+    Ok(merged)
+}
+
+fn allocate_mesh(
+    arena: &Arena,
+    element_type: &ElementType,
+    vertex_count: usize,
+    element_count: usize,
+) -> Result<Mesh, MeshError> {
+    let elements = match element_type {
         ElementType::Point => Element::Point(arena.allocate::<u32>(element_count)?),
         ElementType::Line => Element::Line(arena.allocate::<Vec2u>(element_count)?),
         ElementType::Triangle => Element::Triangle(arena.allocate::<Vec3u>(element_count)?),
         ElementType::Quad => Element::Quad(arena.allocate::<Vec4u>(element_count)?),
     };
 
-    let mut positions = arena.allocate::<Vec3>(vertex_count)?;
-    let mut normals = arena.allocate::<Vec3>(vertex_count)?;
-    let mut texcoords = arena.allocate::<Vec2>(vertex_count)?;
+    Ok(Mesh {
+        elements,
+        vertices: VertexData {
+            positions: arena.allocate::<Vec3>(vertex_count)?,
+            normals: arena.allocate::<Vec3>(vertex_count)?,
+            texcoords: arena.allocate::<Vec2>(vertex_count)?,
+        },
+    })
+}
+
+// Copies the meshes one after the other into `merged`, which has to be sized
+// for all of them.
+fn copy_meshes(
+    meshes: &[Mesh],
+    element_type: &ElementType,
+    merged: &mut Mesh,
+) -> Result<(), MeshError> {
+    let Mesh {
+        elements,
+        vertices:
+            VertexData {
+                positions,
+                normals,
+                texcoords,
+            },
+    } = merged;
 
     let mut index_offset = 0;
     let mut vertex_offset = 0;
@@ -163,9 +227,9 @@ pub fn merge_meshes(arena: &Arena, meshes: &[Mesh], element_type: ElementType) -
         match element_type {
             ElementType::Point => match mesh.elements() {
                 Element::Point(mesh_indices) => {
-                    let element_indices = match &mut elements {
+                    let element_indices = match &mut *elements {
                         Element::Point(indices) => indices,
-                        _ => return None,
+                        _ => return Err(MeshError::MismatchedElements),
                     };
 
                     element_indices[index_offset..(index_offset + mesh_indices.len())]
@@ -179,13 +243,13 @@ pub fn merge_meshes(arena: &Arena, meshes: &[Mesh], element_type: ElementType) -
 
                     index_offset += mesh_indices.len();
                 }
-                _ => return None,
+                _ => return Err(MeshError::MismatchedElements),
             },
             ElementType::Line => match mesh.elements() {
                 Element::Line(mesh_indices) => {
-                    let element_indices = match &mut elements {
+                    let element_indices = match &mut *elements {
                         Element::Line(indices) => indices,
-                        _ => return None,
+                        _ => return Err(MeshError::MismatchedElements),
                     };
 
                     for i in 0..mesh_indices.len() {
@@ -204,13 +268,13 @@ pub fn merge_meshes(arena: &Arena, meshes: &[Mesh], element_type: ElementType) -
 
                     index_offset += mesh_indices.len();
                 }
-                _ => return None,
+                _ => return Err(MeshError::MismatchedElements),
             },
             ElementType::Triangle => match mesh.elements() {
                 Element::Triangle(mesh_indices) => {
-                    let element_indices = match &mut elements {
+                    let element_indices = match &mut *elements {
                         Element::Triangle(indices) => indices,
-                        _ => return None,
+                        _ => return Err(MeshError::MismatchedElements),
                     };
 
                     for i in 0..mesh_indices.len() {
@@ -231,13 +295,13 @@ pub fn merge_meshes(arena: &Arena, meshes: &[Mesh], element_type: ElementType) -
 
                     index_offset += mesh_indices.len();
                 }
-                _ => return None,
+                _ => return Err(MeshError::MismatchedElements),
             },
             ElementType::Quad => match mesh.elements() {
                 Element::Quad(mesh_indices) => {
-                    let element_indices = match &mut elements {
+                    let element_indices = match &mut *elements {
                         Element::Quad(indices) => indices,
-                        _ => return None,
+                        _ => return Err(MeshError::MismatchedElements),
                     };
 
                     for i in 0..mesh_indices.len() {
@@ -260,7 +324,7 @@ pub fn merge_meshes(arena: &Arena, meshes: &[Mesh], element_type: ElementType) -
 
                     index_offset += mesh_indices.len();
                 }
-                _ => return None,
+                _ => return Err(MeshError::MismatchedElements),
             },
         }
 
@@ -271,21 +335,38 @@ pub fn merge_meshes(arena: &Arena, meshes: &[Mesh], element_type: ElementType) -
         vertex_offset += mesh.len();
     }
 
-    Some(Mesh {
-        elements,
-        vertices: VertexData {
-            positions,
-            normals,
-            texcoords,
-        },
-    })
+    Ok(())
 }
 
-pub fn make_quads(arena: &Arena, steps: Vec2u, scale: Vec2, uvscale: Vec2) -> Option<Mesh> {
-    let mut positions = arena.allocate::<Vec3>(((steps.x + 1) * (steps.y + 1)) as usize)?;
-    let mut normals = arena.allocate::<Vec3>(((steps.x + 1) * (steps.y + 1)) as usize)?;
-    let mut texcoords = arena.allocate::<Vec2>(((steps.x + 1) * (steps.y + 1)) as usize)?;
-    let mut quads = arena.allocate::<Vec4u>((steps.x * steps.y) as usize)?;
+// Reported for element counts that don't fit, before anything is allocated.
+fn too_large(arena: &Arena) -> AllocError {
+    arena.alloc_error(usize::MAX, core::mem::align_of::<Vec3>())
+}
+
+// Vertex and quad counts of a grid, rejected when the vertices can't be indexed
+// with u32 elements.
+fn grid_size(arena: &Arena, steps: Vec2u) -> Result<(usize, usize), MeshError> {
+    let vertices = steps
+        .x
+        .checked_add(1)
+        .zip(steps.y.checked_add(1))
+        .and_then(|(columns, rows)| columns.checked_mul(rows))
+        .ok_or_else(|| too_large(arena))?;
+
+    Ok((vertices as usize, steps.x as usize * steps.y as usize))
+}
+
+pub fn make_quads(
+    arena: &Arena,
+    steps: Vec2u,
+    scale: Vec2,
+    uvscale: Vec2,
+) -> Result<Mesh, MeshError> {
+    let (vertex_count, quad_count) = grid_size(arena, steps)?;
+    let mut positions = arena.allocate::<Vec3>(vertex_count)?;
+    let mut normals = arena.allocate::<Vec3>(vertex_count)?;
+    let mut texcoords = arena.allocate::<Vec2>(vertex_count)?;
+    let mut quads = arena.allocate::<Vec4u>(quad_count)?;
 
     for y in 0..(steps.y + 1) {
         for x in 0..(steps.x + 1) {
@@ -314,7 +395,7 @@ pub fn make_quads(arena: &Arena, steps: Vec2u, scale: Vec2, uvscale: Vec2) -> Op
         }
     }
 
-    Some(Mesh {
+    Ok(Mesh {
         vertices: VertexData {
             positions,
             normals,
@@ -324,7 +405,12 @@ pub fn make_quads(arena: &Arena, steps: Vec2u, scale: Vec2, uvscale: Vec2) -> Op
     })
 }
 
-pub fn make_rect(arena: &Arena, steps: Vec2u, scale: Vec2, uvscale: Vec2) -> Option<Mesh> {
+pub fn make_rect(
+    arena: &Arena,
+    steps: Vec2u,
+    scale: Vec2,
+    uvscale: Vec2,
+) -> Result<Mesh, MeshError> {
     make_quads(arena, steps, scale, uvscale)
 }
 
@@ -334,7 +420,7 @@ pub fn make_bulged_rect(
     scale: Vec2,
     uvscale: Vec2,
     height: f32,
-) -> Option<Mesh> {
+) -> Result<Mesh, MeshError> {
     let mut rect = make_rect(arena, steps, scale, uvscale)?;
 
     if height != 0.0 {
@@ -353,10 +439,15 @@ pub fn make_bulged_rect(
         }
     }
 
-    Some(rect)
+    Ok(rect)
 }
 
-pub fn make_recty(arena: &Arena, steps: Vec2u, scale: Vec2, uvscale: Vec2) -> Option<Mesh> {
+pub fn make_recty(
+    arena: &Arena,
+    steps: Vec2u,
+    scale: Vec2,
+    uvscale: Vec2,
+) -> Result<Mesh, MeshError> {
     let mut rect = make_rect(arena, steps, scale, uvscale)?;
 
     for position in rect.vertices.positions.iter_mut() {
@@ -367,7 +458,7 @@ pub fn make_recty(arena: &Arena, steps: Vec2u, scale: Vec2, uvscale: Vec2) -> Op
         *normal = Vec3::new(normal.x, normal.z, normal.y);
     }
 
-    Some(rect)
+    Ok(rect)
 }
 
 pub fn make_bulged_recty(
@@ -376,7 +467,7 @@ pub fn make_bulged_recty(
     scale: Vec2,
     uvscale: Vec2,
     height: f32,
-) -> Option<Mesh> {
+) -> Result<Mesh, MeshError> {
     let mut rect = make_bulged_rect(arena, steps, scale, uvscale, height)?;
 
     for position in rect.vertices.positions.iter_mut() {
@@ -387,42 +478,79 @@ pub fn make_bulged_recty(
         *normal = Vec3::new(normal.x, normal.z, normal.y);
     }
 
-    Some(rect)
+    Ok(rect)
 }
 
-pub fn make_box(arena: &Arena, steps: Vec3u, scale: Vec3, uvscale: Vec3) -> Option<Mesh> {
+pub fn make_box(
+    arena: &Arena,
+    steps: Vec3u,
+    scale: Vec3,
+    uvscale: Vec3,
+) -> Result<Mesh, MeshError> {
+    let faces = [
+        Vec2u::new(steps.x, steps.y),
+        Vec2u::new(steps.z, steps.y),
+        Vec2u::new(steps.x, steps.z),
+    ];
+
+    let mut vertex_count = 0usize;
+    let mut quad_count = 0usize;
+
+    for face in faces {
+        let (vertices, quads) = grid_size(arena, face)?;
+        let overflow = || too_large(arena);
+
+        vertex_count = vertices
+            .checked_mul(2)
+            .and_then(|vertices| vertex_count.checked_add(vertices))
+            .ok_or_else(overflow)?;
+        quad_count = quads
+            .checked_mul(2)
+            .and_then(|quads| quad_count.checked_add(quads))
+            .ok_or_else(overflow)?;
+    }
+
+    if quad_count == 0 {
+        return Err(MeshError::Empty);
+    }
+
+    // Only the merged box is kept. It is allocated first so that the faces can
+    // be built above it in scratch space that is released on return.
+    let mut merged = allocate_mesh(arena, &ElementType::Quad, vertex_count, quad_count)?;
+    let scratch = arena.scratch();
+
     let mut z_plus = make_rect(
-        arena,
+        &scratch,
         Vec2u::new(steps.x, steps.y),
         Vec2::new(scale.x, scale.y),
         Vec2::new(uvscale.x, uvscale.y),
     )?;
     let mut z_minus = make_rect(
-        arena,
+        &scratch,
         Vec2u::new(steps.x, steps.y),
         Vec2::new(scale.x, scale.y),
         Vec2::new(uvscale.x, uvscale.y),
     )?;
     let mut x_plus = make_recty(
-        arena,
+        &scratch,
         Vec2u::new(steps.z, steps.y),
         Vec2::new(scale.z, scale.y),
         Vec2::new(uvscale.z, uvscale.y),
     )?;
     let mut x_minus = make_recty(
-        arena,
+        &scratch,
         Vec2u::new(steps.z, steps.y),
         Vec2::new(scale.z, scale.y),
         Vec2::new(uvscale.z, uvscale.y),
     )?;
     let mut y_plus = make_rect(
-        arena,
+        &scratch,
         Vec2u::new(steps.x, steps.z),
         Vec2::new(scale.x, scale.z),
         Vec2::new(uvscale.x, uvscale.z),
     )?;
     let mut y_minus = make_rect(
-        arena,
+        &scratch,
         Vec2u::new(steps.x, steps.z),
         Vec2::new(scale.x, scale.z),
         Vec2::new(uvscale.x, uvscale.z),
@@ -489,7 +617,9 @@ pub fn make_box(arena: &Arena, steps: Vec3u, scale: Vec3, uvscale: Vec3) -> Opti
     }
 
     let faces = [z_plus, z_minus, x_plus, x_minus, y_plus, y_minus];
-    merge_meshes(arena, &faces, ElementType::Quad)
+    copy_meshes(&faces, &ElementType::Quad, &mut merged)?;
+
+    Ok(merged)
 }
 
 pub fn make_rounded_box(
@@ -498,7 +628,7 @@ pub fn make_rounded_box(
     scale: Vec3,
     uvscale: Vec3,
     radius: f32,
-) -> Option<Mesh> {
+) -> Result<Mesh, MeshError> {
     let mut box_mesh = make_box(arena, steps, scale, uvscale)?;
 
     if radius != 0.0 {
@@ -543,13 +673,19 @@ pub fn make_rounded_box(
         }
     }
 
-    Some(box_mesh)
+    Ok(box_mesh)
 }
 
-pub fn make_rect_stack(arena: &Arena, steps: Vec3u, scale: Vec3, uvscale: Vec2) -> Option<Mesh> {
-    let mut meshes = arena.allocate::<Mesh>((steps.z + 1) as usize)?;
+pub fn make_rect_stack(
+    arena: &Arena,
+    steps: Vec3u,
+    scale: Vec3,
+    uvscale: Vec2,
+) -> Result<Mesh, MeshError> {
+    let layers = steps.z.checked_add(1).ok_or_else(|| too_large(arena))?;
+    let mut meshes = ArenaVec::with_capacity(arena, layers as usize)?;
 
-    for i in 0..(steps.z + 1) {
+    for i in 0..layers {
         let mut mesh = make_rect(
             arena,
             Vec2u::new(steps.x, steps.y),
@@ -561,13 +697,18 @@ pub fn make_rect_stack(arena: &Arena, steps: Vec3u, scale: Vec3, uvscale: Vec2)
             position.z = (-1.0 + 2.0 * i as f32 / steps.z as f32) * scale.z;
         }
 
-        meshes[i as usize] = mesh;
+        meshes.push(mesh)?;
     }
 
     merge_meshes(arena, &meshes, ElementType::Quad)
 }
 
-pub fn make_floor(arena: &Arena, steps: Vec2u, scale: Vec2, uvscale: Vec2) -> Option<Mesh> {
+pub fn make_floor(
+    arena: &Arena,
+    steps: Vec2u,
+    scale: Vec2,
+    uvscale: Vec2,
+) -> Result<Mesh, MeshError> {
     let mut mesh = make_rect(arena, steps, scale, uvscale)?;
 
     for position in mesh.vertices.positions.iter_mut() {
@@ -578,10 +719,10 @@ pub fn make_floor(arena: &Arena, steps: Vec2u, scale: Vec2, uvscale: Vec2) -> Op
         *normal = Vec3::new(normal.x, normal.z, normal.y);
     }
 
-    Some(mesh)
+    Ok(mesh)
 }
 
-pub fn make_sphere(arena: &Arena, steps: u32, scale: f32, uvscale: f32) -> Option<Mesh> {
+pub fn make_sphere(arena: &Arena, steps: u32, scale: f32, uvscale: f32) -> Result<Mesh, MeshError> {
     let mut mesh = make_box(
         arena,
         Vec3u::new(steps, steps, steps),
@@ -600,10 +741,15 @@ pub fn make_sphere(arena: &Arena, steps: u32, scale: f32, uvscale: f32) -> Optio
         *normal = pn;
     }
 
-    Some(mesh)
+    Ok(mesh)
 }
 
-pub fn make_uv_sphere(arena: &Arena, steps: Vec2u, scale: f32, uvscale: Vec2) -> Option<Mesh> {
+pub fn make_uv_sphere(
+    arena: &Arena,
+    steps: Vec2u,
+    scale: f32,
+    uvscale: Vec2,
+) -> Result<Mesh, MeshError> {
     let mut mesh = make_rect(arena, steps, Vec2::new(1.0, 1.0), Vec2::new(1.0, 1.0))?;
 
     for i in 0..mesh.vertices.positions.len() {
@@ -620,10 +766,15 @@ pub fn make_uv_sphere(arena: &Arena, steps: Vec2u, scale: f32, uvscale: Vec2) ->
         mesh.vertices.texcoords[i] = uv * uvscale;
     }
 
-    Some(mesh)
+    Ok(mesh)
 }
 
-pub fn make_uv_sphere_y(arena: &Arena, steps: Vec2u, scale: f32, uvscale: Vec2) -> Option<Mesh> {
+pub fn make_uv_sphere_y(
+    arena: &Arena,
+    steps: Vec2u,
+    scale: f32,
+    uvscale: Vec2,
+) -> Result<Mesh, MeshError> {
     let mut mesh = make_uv_sphere(arena, steps, scale, uvscale)?;
 
     for position in mesh.vertices.positions.iter_mut() {
@@ -640,14 +791,14 @@ pub fn make_uv_sphere_y(arena: &Arena, steps: Vec2u, scale: f32, uvscale: Vec2)
 
     let elements = match mesh.elements {
         Element::Quad(ref mut quads) => quads,
-        _ => return None,
+        _ => return Err(MeshError::MismatchedElements),
     };
 
     for quad in elements.iter_mut() {
         *quad = Vec4u::new(quad.x, quad.w, quad.z, quad.y);
     }
 
-    Some(mesh)
+    Ok(mesh)
 }
 
 pub fn make_capped_uvsphere(
@@ -656,7 +807,7 @@ pub fn make_capped_uvsphere(
     scale: f32,
     uvscale: Vec2,
     cap: f32,
-) -> Option<Mesh> {
+) -> Result<Mesh, MeshError> {
     let mut mesh = make_uv_sphere(arena, steps, scale, uvscale)?;
 
     if cap != 0.0 {
@@ -680,7 +831,7 @@ pub fn make_capped_uvsphere(
         }
     }
 
-    Some(mesh)
+    Ok(mesh)
 }
 
 pub fn make_capped_uvsphere_y(
@@ -689,7 +840,7 @@ pub fn make_capped_uvsphere_y(
     scale: f32,
     uvscale: Vec2,
     cap: f32,
-) -> Option<Mesh> {
+) -> Result<Mesh, MeshError> {
     let mut mesh = make_uv_sphere_y(arena, steps, scale, uvscale)?;
 
     if cap != 0.0 {
@@ -713,10 +864,10 @@ pub fn make_capped_uvsphere_y(
         }
     }
 
-    Some(mesh)
+    Ok(mesh)
 }
 
-pub fn make_disk(arena: &Arena, steps: u32, scale: f32, uvscale: f32) -> Option<Mesh> {
+pub fn make_disk(arena: &Arena, steps: u32, scale: f32, uvscale: f32) -> Result<Mesh, MeshError> {
     let mut mesh = make_rect(
         arena,
         Vec2u::new(steps, steps),
@@ -738,14 +889,14 @@ pub fn make_disk(arena: &Arena, steps: u32, scale: f32, uvscale: f32) -> Option<
 
     let elements = match mesh.elements {
         Element::Quad(ref mut quads) => quads,
-        _ => return None,
+        _ => return Err(MeshError::MismatchedElements),
     };
 
     for quad in elements.iter_mut() {
         *quad = Vec4u::new(quad.x, quad.w, quad.z, quad.y);
     }
 
-    Some(mesh)
+    Ok(mesh)
 }
 
 pub fn make_bulged_disk(
@@ -754,7 +905,7 @@ pub fn make_bulged_disk(
     scale: f32,
     uvscale: f32,
     height: f32,
-) -> Option<Mesh> {
+) -> Result<Mesh, MeshError> {
     let mut disk = make_disk(arena, steps, scale, uvscale)?;
 
     if height != 0.0 {
@@ -773,10 +924,15 @@ pub fn make_bulged_disk(
         }
     }
 
-    Some(disk)
+    Ok(disk)
 }
 
-pub fn make_uv_disk(arena: &Arena, steps: Vec2u, scale: f32, uvscale: Vec2) -> Option<Mesh> {
+pub fn make_uv_disk(
+    arena: &Arena,
+    steps: Vec2u,
+    scale: f32,
+    uvscale: Vec2,
+) -> Result<Mesh, MeshError> {
     let mut disk = make_rect(arena, steps, Vec2::new(1.0, 1.0), Vec2::new(1.0, 1.0))?;
 
     for i in 0..disk.vertices.positions.len() {
@@ -788,7 +944,7 @@ pub fn make_uv_disk(arena: &Arena, steps: Vec2u, scale: f32, uvscale: Vec2) -> O
         disk.vertices.texcoords[i] = uv * uvscale;
     }
 
-    Some(disk)
+    Ok(disk)
 }
 
 pub fn make_lines(
@@ -797,22 +953,18 @@ pub fn make_lines(
     scale: Vec2,
     uvscale: Vec2,
     rad: Vec2,
-) -> Option<Mesh> {
-    let mut positions = arena
-        .allocate::<Vec3>(((steps.x + 1) * steps.y) as usize)
-        .unwrap();
-    let mut normals = arena
-        .allocate::<Vec3>(((steps.x + 1) * steps.y) as usize)
-        .unwrap();
-    let mut texcoords = arena
-        .allocate::<Vec2>(((steps.x + 1) * steps.y) as usize)
-        .unwrap();
-    let mut radius = arena
-        .allocate::<f32>(((steps.x + 1) * steps.y) as usize)
-        .unwrap();
-    let mut lines = arena
-        .allocate::<Vec2u>((steps.x * steps.y) as usize)
-        .unwrap();
+) -> Result<Mesh, MeshError> {
+    let vertex_count = steps
+        .x
+        .checked_add(1)
+        .and_then(|columns| columns.checked_mul(steps.y))
+        .ok_or_else(|| too_large(arena))? as usize;
+    let line_count = steps.x as usize * steps.y as usize;
+    let mut positions = arena.allocate::<Vec3>(vertex_count)?;
+    let mut normals = arena.allocate::<Vec3>(vertex_count)?;
+    let mut texcoords = arena.allocate::<Vec2>(vertex_count)?;
+    let mut radius = arena.allocate::<f32>(vertex_count)?;
+    let mut lines = arena.allocate::<Vec2u>(line_count)?;
 
     if steps.y > 1 {
         for y in 0..steps.y {
@@ -845,7 +997,7 @@ pub fn make_lines(
         }
     }
 
-    Some(Mesh {
+    Ok(Mesh {
         vertices: VertexData {
             positions,
             normals,
@@ -855,8 +1007,12 @@ pub fn make_lines(
     })
 }
 
-pub fn quads_to_triangles(arena: &Arena, quads: &ArenaSlice<Vec4u>) -> Option<ArenaSlice<Vec3u>> {
-    let mut triangles = arena.allocate::<Vec3u>(quads.len() * 2)?;
+pub fn quads_to_triangles(
+    arena: &Arena,
+    quads: &ArenaSlice<Vec4u>,
+) -> Result<ArenaSlice<Vec3u>, AllocError> {
+    let count = quads.len().checked_mul(2).ok_or_else(|| too_large(arena))?;
+    let mut triangles = arena.allocate::<Vec3u>(count)?;
     let mut triangle_count = 0;
 
     for quad in quads.iter() {
@@ -869,20 +1025,20 @@ pub fn quads_to_triangles(arena: &Arena, quads: &ArenaSlice<Vec4u>) -> Option<Ar
         }
     }
 
-    Some(triangles)
+    Ok(triangles)
 }
 
 pub fn triangles_to_quads(
     arena: &Arena,
     triangles: ArenaSlice<Vec3u>,
-) -> Option<ArenaSlice<Vec4u>> {
+) -> Result<ArenaSlice<Vec4u>, AllocError> {
     let mut quads = arena.allocate::<Vec4u>(triangles.len())?;
 
     for (quad_count, triangle) in triangles.iter().enumerate() {
         quads[quad_count] = Vec4u::new(triangle.x, triangle.y, triangle.z, triangle.z);
     }
 
-    Some(quads)
+    Ok(quads)
 }
 
 #[cfg(test)]
@@ -986,4 +1142,92 @@ mod tests {
             1
         );
     }
+
+    #[test]
+    fn test_make_box_keeps_only_merged_mesh() {
+        let arena = Arena::new(4096);
+
+        let mesh = make_box(
+            &arena,
+            Vec3u::new(1, 2, 1),
+            Vec3::new(1.0, 1.0, 1.0),
+            Vec3::new(1.0, 1.0, 1.0),
+        )
+        .unwrap();
+
+        assert_eq!(mesh.len(), 2 * (6 + 6 + 4));
+        assert!(matches!(mesh.elements(), Element::Quad(quads) if quads.len() == 2 * (2 + 2 + 1)));
+        assert_eq!(
+            arena.occupied(),
+            mesh.len() * core::mem::size_of::<[Vec3; 2]>()
+                + mesh.len() * core::mem::size_of::<Vec2>()
+                + 10 * core::mem::size_of::<Vec4u>()
+        );
+        assert!(mesh
+            .positions()
+            .iter()
+            .all(|p| p.abs().max_element() == 1.0));
+    }
+
+    #[test]
+    fn test_make_rect_stack() {
+        let arena = Arena::new(4096);
+
+        let mesh = make_rect_stack(
+            &arena,
+            Vec3u::new(1, 1, 2),
+            Vec3::new(1.0, 1.0, 1.0),
+            Vec2::new(1.0, 1.0),
+        )
+        .unwrap();
+
+        assert_eq!(mesh.len(), 3 * 4);
+        assert_eq!(mesh.positions()[0].z, -1.0);
+        assert_eq!(mesh.positions()[4].z, 0.0);
+        assert_eq!(mesh.positions()[8].z, 1.0);
+    }
+
+    #[test]
+    fn test_mesh_errors() {
+        let arena = Arena::new(64);
+
+        let error = make_quads(
+            &arena,
+            Vec2u::new(4, 4),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(1.0, 1.0),
+        )
+        .err()
+        .unwrap();
+
+        assert!(matches!(error, MeshError::Alloc(_)));
+
+        let error = make_quads(
+            &arena,
+            Vec2u::new(u32::MAX, 1),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(1.0, 1.0),
+        )
+        .err()
+        .unwrap();
+
+        assert!(matches!(error, MeshError::Alloc(_)));
+
+        let error = make_lines(
+            &arena,
+            Vec2u::new(1 << 16, 1 << 16),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(0.1, 0.1),
+        )
+        .err()
+        .unwrap();
+
+        assert!(matches!(error, MeshError::Alloc(_)));
+        assert_eq!(arena.occupied(), 0);
+        assert_eq!(
+            merge_meshes(&arena, &[], ElementType::Quad).err(),
+            Some(MeshError::Empty)
+        );
+    }
 }
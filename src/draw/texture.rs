@@ -1,4 +1,4 @@
-use crate::arena::{Arena, ArenaSlice};
+use crate::arena::{AllocError, Arena, ArenaSlice};
 
 pub struct Texture {
     width: u32,
@@ -8,16 +8,19 @@ pub struct Texture {
 }
 
 impl Texture {
-    pub fn new(arena: &Arena, width: u32, height: u32, channels: u32) -> Self {
-        let data = arena
-            .allocate::<u8>(width as usize * height as usize * channels as usize)
-            .unwrap();
-        Self {
+    pub fn new(arena: &Arena, width: u32, height: u32, channels: u32) -> Result<Self, AllocError> {
+        let size = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|size| size.checked_mul(channels as usize))
+            .ok_or(arena.alloc_error(usize::MAX, 1))?;
+        let data = arena.allocate::<u8>(size)?;
+
+        Ok(Self {
             width,
             height,
             channels,
             data,
-        }
+        })
     }
 
     pub fn width(&self) -> u32 {
@@ -1,3 +1,4 @@
+use crate::arena::FrameArena;
 use crate::math::Vec2;
 use core::cell::{Cell, RefCell};
 use tao::event::MouseButton;
@@ -17,6 +18,8 @@ use delta::Delta;
 use keyboard::Keyboard;
 use mouse::Mouse;
 
+const FRAME_ARENA_SIZE: usize = 1024 * 1024;
+
 pub struct Environment {
     initialized: Cell<bool>,
     quit: Cell<bool>,
@@ -25,6 +28,7 @@ pub struct Environment {
     mouse: Mouse,
     keyboard: Keyboard,
     clock: Clock,
+    frames: FrameArena,
 }
 
 impl Environment {
@@ -32,6 +36,7 @@ impl Environment {
         let mouse = Mouse::default();
         let keyboard = Keyboard::new();
         let clock = Clock::new();
+        let frames = FrameArena::new(FRAME_ARENA_SIZE);
 
         Self {
             initialized: Cell::new(false),
@@ -40,6 +45,7 @@ impl Environment {
             mouse,
             keyboard,
             clock,
+            frames,
         }
     }
 
@@ -80,6 +86,10 @@ impl Environment {
         &self.clock
     }
 
+    pub fn frames(&self) -> &FrameArena {
+        &self.frames
+    }
+
     pub fn window_title(&self) -> String {
         self.window.title()
     }
@@ -157,6 +167,7 @@ impl Environment {
                 }
 
                 clock.update();
+                self.frames.swap();
             }
             _ => (),
         }
@@ -0,0 +1,169 @@
+use super::Interned;
+use crate::arena::AtomicArena;
+use crate::hash::{self, FxBuildHasher};
+use alloc::vec::Vec;
+use std::collections::HashSet;
+use std::sync::{Mutex, OnceLock};
+
+const SHARDS: usize = 16;
+const CHUNK_SIZE: usize = 64 * 1024;
+
+// A process-wide interner. Strings are spread over independently locked shards
+// so threads interning different strings rarely contend.
+pub struct GlobalPool {
+    shards: [Mutex<Shard>; SHARDS],
+}
+
+#[derive(Default)]
+struct Shard {
+    // The keys point into `chunks`, whose buffers never move or get cleared
+    // while the pool is alive.
+    lookup: HashSet<&'static str, FxBuildHasher>,
+    chunks: Vec<AtomicArena>,
+}
+
+impl Shard {
+    fn insert(&mut self, value: &str) -> &'static str {
+        if let Some(interned) = self.lookup.get(value) {
+            return interned;
+        }
+
+        let stored = match self.chunks.last().map(|chunk| chunk.push_str(value)) {
+            Some(Ok(stored)) => &*stored,
+            _ => {
+                self.chunks
+                    .push(AtomicArena::new(CHUNK_SIZE.max(value.len())));
+                let chunk = self.chunks.last().unwrap();
+                &*chunk
+                    .push_str(value)
+                    .expect("chunk is sized for the string")
+            }
+        };
+
+        let stored = unsafe { &*(stored as *const str) };
+        self.lookup.insert(stored);
+        stored
+    }
+}
+
+impl GlobalPool {
+    pub fn new() -> GlobalPool {
+        GlobalPool {
+            shards: core::array::from_fn(|_| Mutex::new(Shard::default())),
+        }
+    }
+
+    // The shard index comes from the middle of the hash, the table inside the
+    // shard uses the low and high bits.
+    fn shard(&self, value: &str) -> &Mutex<Shard> {
+        &self.shards[(hash::hash(value) >> 32) as usize % SHARDS]
+    }
+
+    pub fn intern(&self, value: &str) -> Interned<'_> {
+        let mut shard = self.shard(value).lock().unwrap();
+        Interned(shard.insert(value))
+    }
+
+    pub fn get(&self, value: &str) -> Option<Interned<'_>> {
+        let shard = self.shard(value).lock().unwrap();
+        shard.lookup.get(value).map(|interned| Interned(interned))
+    }
+
+    pub fn contains(&self, value: &str) -> bool {
+        self.get(value).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.lock().unwrap().lookup.len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for GlobalPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn global() -> &'static GlobalPool {
+    static POOL: OnceLock<GlobalPool> = OnceLock::new();
+    POOL.get_or_init(GlobalPool::new)
+}
+
+// Interns a string literal in the global pool on first use, later evaluations
+// return the cached handle without touching the pool.
+#[macro_export]
+macro_rules! intern {
+    ($value:literal) => {{
+        static CACHE: std::sync::OnceLock<$crate::intern::Interned<'static>> =
+            std::sync::OnceLock::new();
+        *CACHE.get_or_init(|| $crate::intern::global().intern($value))
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_global_pool() {
+        let pool = GlobalPool::new();
+        let a = pool.intern("hello");
+        let b = pool.intern(&alloc::string::String::from("hello"));
+
+        assert_eq!(a, b);
+        assert_eq!(a.as_ptr(), b.as_ptr());
+        assert_ne!(a, pool.intern("world"));
+        assert_eq!(pool.get("hello"), Some(a));
+        assert!(!pool.contains("missing"));
+        assert_eq!(pool.len(), 2);
+
+        let large = "x".repeat(CHUNK_SIZE * 2);
+        assert_eq!(pool.intern(&large).as_str(), large);
+        assert_eq!(pool.intern("hello"), a);
+    }
+
+    #[test]
+    fn test_global_pool_threads() {
+        let pool = GlobalPool::new();
+        let names: Vec<_> = (0..1000).map(|i| alloc::format!("name-{}", i)).collect();
+
+        let results: Vec<Vec<Interned<'_>>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..8)
+                .map(|_| scope.spawn(|| names.iter().map(|name| pool.intern(name)).collect()))
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        });
+
+        assert_eq!(pool.len(), names.len());
+
+        for result in &results[1..] {
+            assert_eq!(result, &results[0]);
+        }
+
+        for (name, interned) in names.iter().zip(&results[0]) {
+            assert_eq!(interned, &name.as_str());
+        }
+    }
+
+    #[test]
+    fn test_intern_macro() {
+        let lookup = || crate::intern!("monolith");
+        let a = lookup();
+        let b = lookup();
+
+        assert_eq!(a, b);
+        assert_eq!(a, global().intern("monolith"));
+        assert_eq!(a, "monolith");
+    }
+}
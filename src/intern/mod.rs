@@ -0,0 +1,486 @@
+use crate::arena::{AllocError, Arena, ArenaMap};
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::cell::RefCell;
+use core::cmp::PartialEq;
+use core::hash::{Hash, Hasher};
+use core::ops::Deref;
+
+mod global;
+mod path;
+
+pub use global::{global, GlobalPool};
+pub use path::{PathId, PathPool};
+
+pub struct StrPool {
+    arena: RefCell<Arena>,
+    table: Arena,
+    lookup: RefCell<ArenaMap<StrIntern, u32>>,
+    symbols: RefCell<ArenaMap<u32, StrIntern>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Symbol(u32);
+
+// A string owned by a StrPool. Every distinct string is stored once, so equality
+// and hashing only need to look at the address.
+#[derive(Clone, Copy)]
+pub struct Interned<'pool>(&'pool str);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PoolError {
+    Alloc(AllocError),
+    Truncated,
+    InvalidUtf8,
+    Duplicate,
+    TrailingBytes,
+    TooLarge,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct StrIntern {
+    data: *const u8,
+    len: usize,
+}
+
+impl Deref for StrIntern {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { core::str::from_utf8_unchecked(core::slice::from_raw_parts(self.data, self.len)) }
+    }
+}
+
+impl PartialEq for StrIntern {
+    fn eq(&self, other: &Self) -> bool {
+        self.deref() == other.deref()
+    }
+}
+
+impl Eq for StrIntern {}
+
+impl Hash for StrIntern {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.deref().hash(state);
+    }
+}
+
+impl Borrow<str> for StrIntern {
+    fn borrow(&self) -> &str {
+        self
+    }
+}
+
+impl<'pool> Interned<'pool> {
+    pub fn as_str(&self) -> &'pool str {
+        self.0
+    }
+}
+
+impl Deref for Interned<'_> {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
+
+impl AsRef<str> for Interned<'_> {
+    fn as_ref(&self) -> &str {
+        self.0
+    }
+}
+
+impl PartialEq for Interned<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        core::ptr::eq(self.0, other.0)
+    }
+}
+
+impl Eq for Interned<'_> {}
+
+impl Hash for Interned<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.as_ptr().hash(state);
+        self.0.len().hash(state);
+    }
+}
+
+impl PartialEq<str> for Interned<'_> {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Interned<'_> {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl core::fmt::Debug for Interned<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Debug::fmt(self.0, f)
+    }
+}
+
+impl core::fmt::Display for Interned<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Display::fmt(self.0, f)
+    }
+}
+
+impl Symbol {
+    pub fn id(self) -> u32 {
+        self.0
+    }
+}
+
+impl From<AllocError> for PoolError {
+    fn from(error: AllocError) -> Self {
+        PoolError::Alloc(error)
+    }
+}
+
+impl core::fmt::Display for PoolError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            PoolError::Alloc(error) => write!(f, "{}", error),
+            PoolError::Truncated => write!(f, "string pool data is truncated"),
+            PoolError::InvalidUtf8 => write!(f, "string pool data is not valid UTF-8"),
+            PoolError::Duplicate => write!(f, "string pool data contains a duplicate string"),
+            PoolError::TrailingBytes => write!(f, "string pool data has trailing bytes"),
+            PoolError::TooLarge => write!(f, "string pool does not fit the serialized format"),
+        }
+    }
+}
+
+impl std::error::Error for PoolError {}
+
+impl StrPool {
+    pub fn new(size: usize) -> StrPool {
+        StrPool {
+            arena: RefCell::new(Arena::growable(size)),
+            table: Arena::growable(size),
+            lookup: RefCell::new(ArenaMap::new()),
+            symbols: RefCell::new(ArenaMap::new()),
+        }
+    }
+
+    fn insert(&self, value: &str) -> Result<(StrIntern, u32), AllocError> {
+        if let Some((intern, id)) = self.lookup.borrow().get_key_value(value) {
+            return Ok((*intern, *id));
+        }
+
+        let mut lookup = self.lookup.borrow_mut();
+        let mut symbols = self.symbols.borrow_mut();
+        let overflow = || {
+            self.table
+                .alloc_error(usize::MAX, core::mem::align_of::<u32>())
+        };
+        let id = u32::try_from(symbols.len()).map_err(|_| overflow())?;
+
+        // Both tables make room first so they can't fall out of step.
+        lookup.reserve(&self.table, 1)?;
+        symbols.reserve(&self.table, 1)?;
+
+        let arena = self.arena.borrow();
+        let string = arena.push_string(value)?;
+        let intern = StrIntern {
+            data: string.as_ptr(),
+            len: string.len(),
+        };
+
+        lookup.insert(&self.table, intern, id)?;
+        symbols.insert(&self.table, id, intern)?;
+
+        Ok((intern, id))
+    }
+
+    fn interned(&self, intern: StrIntern) -> Interned<'_> {
+        Interned(unsafe {
+            core::str::from_utf8_unchecked(core::slice::from_raw_parts(intern.data, intern.len))
+        })
+    }
+
+    pub fn intern(&self, value: &str) -> Result<Interned<'_>, AllocError> {
+        let (intern, _) = self.insert(value)?;
+        Ok(self.interned(intern))
+    }
+
+    pub fn symbol(&self, value: &str) -> Result<Symbol, AllocError> {
+        let (_, id) = self.insert(value)?;
+        Ok(Symbol(id))
+    }
+
+    pub fn get_symbol(&self, value: &str) -> Option<Symbol> {
+        self.lookup.borrow().get(value).map(|id| Symbol(*id))
+    }
+
+    pub fn resolve(&self, symbol: Symbol) -> Interned<'_> {
+        let intern = *self
+            .symbols
+            .borrow()
+            .get(&symbol.0)
+            .expect("symbol does not belong to this pool");
+
+        self.interned(intern)
+    }
+
+    pub fn get(&self, value: &str) -> Option<Interned<'_>> {
+        let intern = *self.lookup.borrow().get_key_value(value)?.0;
+        Some(self.interned(intern))
+    }
+
+    pub fn contains(&self, value: &str) -> bool {
+        self.lookup.borrow().contains_key(value)
+    }
+
+    // Strings are written in symbol order as a little-endian u32 count followed by
+    // u32 length prefixed UTF-8 bytes, so reloading reproduces every symbol id.
+    pub fn serialize(&self) -> Result<Vec<u8>, PoolError> {
+        let symbols = self.symbols.borrow();
+        let size = symbols.values().map(|intern| intern.len + 4).sum::<usize>();
+        let mut bytes = Vec::with_capacity(size + 4);
+        let count = u32::try_from(symbols.len()).map_err(|_| PoolError::TooLarge)?;
+
+        bytes.extend_from_slice(&count.to_le_bytes());
+
+        for id in 0..count {
+            let intern = symbols.get(&id).expect("symbol ids are contiguous");
+            let len = u32::try_from(intern.len).map_err(|_| PoolError::TooLarge)?;
+
+            bytes.extend_from_slice(&len.to_le_bytes());
+            bytes.extend_from_slice(intern.as_bytes());
+        }
+
+        Ok(bytes)
+    }
+
+    pub fn deserialize(bytes: &[u8], size: usize) -> Result<StrPool, PoolError> {
+        fn take<'b>(bytes: &mut &'b [u8], len: usize) -> Result<&'b [u8], PoolError> {
+            if bytes.len() < len {
+                return Err(PoolError::Truncated);
+            }
+
+            let (head, tail) = bytes.split_at(len);
+            *bytes = tail;
+            Ok(head)
+        }
+
+        fn take_u32(bytes: &mut &[u8]) -> Result<u32, PoolError> {
+            let head = take(bytes, 4)?;
+            Ok(u32::from_le_bytes([head[0], head[1], head[2], head[3]]))
+        }
+
+        let pool = StrPool::new(size);
+        let mut bytes = bytes;
+        let count = take_u32(&mut bytes)?;
+
+        for id in 0..count {
+            let len = take_u32(&mut bytes)? as usize;
+            let value =
+                core::str::from_utf8(take(&mut bytes, len)?).map_err(|_| PoolError::InvalidUtf8)?;
+
+            if pool.symbol(value)? != Symbol(id) {
+                return Err(PoolError::Duplicate);
+            }
+        }
+
+        if !bytes.is_empty() {
+            return Err(PoolError::TrailingBytes);
+        }
+
+        Ok(pool)
+    }
+
+    pub fn occupied(&self) -> usize {
+        self.arena.borrow().occupied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.lookup.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lookup.borrow().is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_str_pool() {
+        let pool = StrPool::new(1024);
+        let a = "hello";
+        let b = "world";
+        let e = "goodbye";
+        let f = "world";
+
+        let a_intern: &str = &pool.intern(a).unwrap();
+        let b_intern: &str = &pool.intern(b).unwrap();
+        let c_intern: &str = &pool.intern(a).unwrap();
+        let d_intern: &str = &pool.intern(b).unwrap();
+        let e_intern: &str = &pool.intern(e).unwrap();
+        let f_intern: &str = &pool.intern(f).unwrap();
+
+        assert_ne!(a_intern, b_intern);
+        assert_eq!(a_intern, c_intern);
+        assert_eq!(b_intern, d_intern);
+        assert_ne!(c_intern, d_intern);
+        assert_ne!(a_intern, e_intern);
+        assert_eq!(b_intern, f_intern);
+        assert_ne!(a_intern.as_ptr(), b_intern.as_ptr());
+        assert_eq!(a_intern.as_ptr(), c_intern.as_ptr());
+        assert_eq!(b_intern.as_ptr(), d_intern.as_ptr());
+        assert_ne!(c_intern.as_ptr(), d_intern.as_ptr());
+        assert_ne!(a_intern.as_ptr(), e_intern.as_ptr());
+        assert_eq!(b_intern.as_ptr(), f_intern.as_ptr());
+        assert_eq!(pool.len(), 3);
+        assert_eq!(pool.occupied(), 17);
+    }
+
+    #[test]
+    fn test_str_pool_growth() {
+        let pool = StrPool::new(8);
+        let a = pool.intern("monolith").unwrap().as_str();
+        let b = pool.intern("filesystem").unwrap().as_str();
+        let c = pool.intern("monolith").unwrap().as_str();
+
+        assert_eq!(a, "monolith");
+        assert_eq!(b, "filesystem");
+        assert_eq!(a.as_ptr(), c.as_ptr());
+        assert_eq!(pool.occupied(), 18);
+    }
+
+    #[test]
+    fn test_str_pool_get() {
+        let pool = StrPool::new(1024);
+        let a = pool.intern("hello").unwrap();
+
+        assert!(pool.contains("hello"));
+        assert!(!pool.contains("world"));
+        assert_eq!(pool.get("hello").unwrap().as_ptr(), a.as_ptr());
+        assert_eq!(pool.get("world"), None);
+        assert_eq!(pool.len(), 1);
+    }
+
+    #[test]
+    fn test_str_pool_scales() {
+        // Mean slots probed per lookup, which has to stay flat as the pool grows.
+        let mean_probes = |count: usize| {
+            let pool = StrPool::new(64 * 1024);
+            let names: Vec<String> = (0..count)
+                .map(|i| format!("assets/level/{}.mesh", i))
+                .collect();
+
+            let interned: Vec<*const u8> = names
+                .iter()
+                .map(|name| pool.intern(name).unwrap().as_ptr())
+                .collect();
+
+            for (name, ptr) in names.iter().zip(&interned) {
+                assert_eq!(pool.intern(name).unwrap().as_ptr(), *ptr);
+            }
+
+            assert_eq!(pool.len(), count);
+            assert!(pool.contains(&format!("assets/level/{}.mesh", count - 1)));
+            assert!(!pool.contains(&format!("assets/level/{}.mesh", count)));
+
+            let lookup = pool.lookup.borrow();
+            let probes = names
+                .iter()
+                .map(|name| lookup.probes(name.as_str()).unwrap())
+                .sum::<usize>();
+
+            probes as f64 / count as f64
+        };
+
+        let small = mean_probes(1_000);
+        let large = mean_probes(100_000);
+
+        assert!(small < 4.0, "{} probes per lookup at 1k strings", small);
+        assert!(large < 4.0, "{} probes per lookup at 100k strings", large);
+        assert!(
+            large < small * 2.0,
+            "{} probes at 100k vs {} at 1k",
+            large,
+            small
+        );
+    }
+
+    #[test]
+    fn test_interned() {
+        let pool = StrPool::new(1024);
+        let other = StrPool::new(1024);
+
+        let a = pool.intern("monolith").unwrap();
+        let b = pool.intern(&String::from("monolith")).unwrap();
+        let c = other.intern("monolith").unwrap();
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_eq!(a, "monolith");
+        assert_eq!(c.len(), 8);
+        assert_eq!(crate::hash::hash(&a), crate::hash::hash(&b));
+        assert_eq!(a.to_string(), "monolith");
+    }
+
+    #[test]
+    fn test_str_pool_symbols() {
+        let pool = StrPool::new(1024);
+        let jump = pool.symbol("jump").unwrap();
+        let fire = pool.symbol("fire").unwrap();
+
+        assert_eq!(pool.symbol("jump").unwrap(), jump);
+        assert_ne!(jump, fire);
+        assert!(jump < fire);
+        assert_eq!(pool.resolve(jump), "jump");
+        assert_eq!(pool.resolve(fire), "fire");
+        assert_eq!(pool.get_symbol("fire"), Some(fire));
+        assert_eq!(pool.get_symbol("crouch"), None);
+
+        let interned = pool.intern("crouch").unwrap();
+        let crouch = pool.get_symbol("crouch").unwrap();
+        assert_eq!(pool.resolve(crouch).as_ptr(), interned.as_ptr());
+    }
+
+    #[test]
+    fn test_str_pool_serialize() {
+        let pool = StrPool::new(1024);
+        let symbols: Vec<Symbol> = ["jump", "fire", "assets/wall.png", ""]
+            .iter()
+            .map(|name| pool.symbol(name).unwrap())
+            .collect();
+
+        let bytes = pool.serialize().unwrap();
+        let loaded = StrPool::deserialize(&bytes, 1024).unwrap();
+
+        assert_eq!(loaded.len(), pool.len());
+        for symbol in symbols {
+            assert_eq!(
+                loaded.resolve(symbol).as_str(),
+                pool.resolve(symbol).as_str()
+            );
+            assert_eq!(loaded.symbol(&pool.resolve(symbol)).unwrap(), symbol);
+        }
+
+        assert_eq!(
+            StrPool::deserialize(&bytes[..bytes.len() - 1], 1024).err(),
+            Some(PoolError::Truncated)
+        );
+
+        let mut duplicate = Vec::new();
+        duplicate.extend_from_slice(&2u32.to_le_bytes());
+        duplicate.extend_from_slice(&1u32.to_le_bytes());
+        duplicate.push(b'a');
+        duplicate.extend_from_slice(&1u32.to_le_bytes());
+        duplicate.push(b'a');
+        assert_eq!(
+            StrPool::deserialize(&duplicate, 1024).err(),
+            Some(PoolError::Duplicate)
+        );
+    }
+}
@@ -0,0 +1,274 @@
+use super::{Interned, StrPool, Symbol};
+use crate::arena::{AllocError, Arena, ArenaMap, ArenaString};
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+const NONE: u32 = u32::MAX;
+const ROOT: &str = "/";
+const CURRENT: &str = ".";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PathId(u32);
+
+// A path is its parent's id plus the symbol of its last component, so every
+// directory prefix is stored once no matter how many paths share it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct PathNode {
+    parent: u32,
+    name: Symbol,
+}
+
+pub struct PathPool {
+    strings: StrPool,
+    table: Arena,
+    nodes: RefCell<Vec<PathNode>>,
+    lookup: RefCell<ArenaMap<PathNode, u32>>,
+}
+
+// Empty and "." components are dropped, ".." is kept as written since it can't
+// be folded away without resolving symlinks.
+fn split(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/')
+        .filter(|component| !component.is_empty() && *component != CURRENT)
+}
+
+impl PathId {
+    pub fn id(self) -> u32 {
+        self.0
+    }
+}
+
+impl PathPool {
+    pub fn new(size: usize) -> PathPool {
+        PathPool {
+            strings: StrPool::new(size),
+            table: Arena::growable(size),
+            nodes: RefCell::new(Vec::new()),
+            lookup: RefCell::new(ArenaMap::new()),
+        }
+    }
+
+    fn node(&self, parent: u32, name: &str) -> Result<u32, AllocError> {
+        let key = PathNode {
+            parent,
+            name: self.strings.symbol(name)?,
+        };
+
+        if let Some(id) = self.lookup.borrow().get(&key) {
+            return Ok(*id);
+        }
+
+        let mut nodes = self.nodes.borrow_mut();
+        let id = nodes.len() as u32;
+        self.lookup.borrow_mut().insert(&self.table, key, id)?;
+        nodes.push(key);
+
+        Ok(id)
+    }
+
+    fn find(&self, parent: u32, name: &str) -> Option<u32> {
+        let key = PathNode {
+            parent,
+            name: self.strings.get_symbol(name)?,
+        };
+
+        self.lookup.borrow().get(&key).copied()
+    }
+
+    fn is_current(&self, id: u32) -> bool {
+        if id == NONE {
+            return false;
+        }
+
+        let node = self.nodes.borrow()[id as usize];
+        node.parent == NONE && self.strings.resolve(node.name) == CURRENT
+    }
+
+    fn append(&self, base: u32, path: &str) -> Result<PathId, AllocError> {
+        let mut id = if path.starts_with('/') {
+            self.node(NONE, ROOT)?
+        } else if self.is_current(base) {
+            NONE
+        } else {
+            base
+        };
+
+        for component in split(path) {
+            id = self.node(id, component)?;
+        }
+
+        if id == NONE {
+            id = self.node(NONE, CURRENT)?;
+        }
+
+        Ok(PathId(id))
+    }
+
+    pub fn path(&self, path: &str) -> Result<PathId, AllocError> {
+        self.append(NONE, path)
+    }
+
+    // Joining an absolute path replaces the base, like `std::path::Path::join`.
+    pub fn join(&self, base: PathId, path: &str) -> Result<PathId, AllocError> {
+        self.append(base.0, path)
+    }
+
+    pub fn get(&self, path: &str) -> Option<PathId> {
+        let mut id = if path.starts_with('/') {
+            self.find(NONE, ROOT)?
+        } else {
+            NONE
+        };
+
+        for component in split(path) {
+            id = self.find(id, component)?;
+        }
+
+        if id == NONE {
+            id = self.find(NONE, CURRENT)?;
+        }
+
+        Some(PathId(id))
+    }
+
+    pub fn parent(&self, id: PathId) -> Option<PathId> {
+        let parent = self.nodes.borrow()[id.0 as usize].parent;
+        (parent != NONE).then_some(PathId(parent))
+    }
+
+    pub fn name(&self, id: PathId) -> Interned<'_> {
+        let name = self.nodes.borrow()[id.0 as usize].name;
+        self.strings.resolve(name)
+    }
+
+    pub fn file_name(&self, id: PathId) -> Option<Interned<'_>> {
+        let name = self.name(id);
+
+        match name.as_str() {
+            ROOT | CURRENT | ".." => None,
+            _ => Some(name),
+        }
+    }
+
+    pub fn extension(&self, id: PathId) -> Option<&str> {
+        let name = self.file_name(id)?.as_str();
+
+        match name.rsplit_once('.') {
+            Some((stem, extension)) if !stem.is_empty() => Some(extension),
+            _ => None,
+        }
+    }
+
+    pub fn components(&self, id: PathId) -> Vec<Interned<'_>> {
+        let mut components = Vec::new();
+        let mut current = Some(id);
+
+        while let Some(id) = current {
+            components.push(self.name(id));
+            current = self.parent(id);
+        }
+
+        components.reverse();
+        components
+    }
+
+    pub fn render(&self, arena: &Arena, id: PathId) -> Result<ArenaString, AllocError> {
+        let components = self.components(id);
+        let (absolute, names) = match components.split_first() {
+            Some((first, rest)) if *first == ROOT => (true, rest),
+            _ => (false, &components[..]),
+        };
+
+        if names.is_empty() {
+            return arena.push_string(ROOT);
+        }
+
+        let len = names.iter().map(|name| name.len() + 1).sum::<usize>() - !absolute as usize;
+        let mut buffer = arena.allocate::<u8>(len)?;
+        let mut offset = 0;
+
+        for name in names {
+            if absolute || offset > 0 {
+                buffer[offset] = b'/';
+                offset += 1;
+            }
+
+            buffer[offset..offset + name.len()].copy_from_slice(name.as_bytes());
+            offset += name.len();
+        }
+
+        Ok(ArenaString::from_slice(buffer))
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_pool() {
+        let pool = PathPool::new(1024);
+        let arena = Arena::new(1024);
+
+        let file = pool.path("/usr/share/fonts/mono.ttf").unwrap();
+        let dir = pool.parent(file).unwrap();
+        let other = pool.join(dir, "sans.otf").unwrap();
+
+        assert_eq!(pool.parent(other), Some(dir));
+        assert_eq!(pool.path("/usr//share/./fonts/").unwrap(), dir);
+        assert_eq!(pool.get("/usr/share/fonts/sans.otf"), Some(other));
+        assert_eq!(pool.get("/usr/share/missing"), None);
+        assert_eq!(pool.len(), 6);
+
+        assert_eq!(pool.file_name(file).unwrap(), "mono.ttf");
+        assert_eq!(pool.extension(file), Some("ttf"));
+        assert_eq!(pool.extension(pool.path(".bashrc").unwrap()), None);
+        assert_eq!(
+            pool.extension(pool.path("archive.tar.gz").unwrap()),
+            Some("gz")
+        );
+
+        assert_eq!(
+            pool.render(&arena, file).unwrap(),
+            "/usr/share/fonts/mono.ttf"
+        );
+        assert_eq!(
+            pool.render(&arena, other).unwrap(),
+            "/usr/share/fonts/sans.otf"
+        );
+        assert_eq!(pool.render(&arena, pool.path("/").unwrap()).unwrap(), "/");
+        assert_eq!(pool.file_name(pool.path("/").unwrap()), None);
+    }
+
+    #[test]
+    fn test_path_pool_relative() {
+        let pool = PathPool::new(1024);
+        let arena = Arena::new(1024);
+
+        let current = pool.path(".").unwrap();
+        let src = pool.join(current, "src").unwrap();
+        let lib = pool.join(src, "lib.rs").unwrap();
+
+        assert_eq!(pool.path("").unwrap(), current);
+        assert_eq!(pool.path("./src").unwrap(), src);
+        assert_eq!(pool.parent(src), None);
+        assert_eq!(pool.render(&arena, current).unwrap(), ".");
+        assert_eq!(pool.render(&arena, lib).unwrap(), "src/lib.rs");
+        assert_eq!(
+            pool.render(&arena, pool.join(lib, "../mod.rs").unwrap())
+                .unwrap(),
+            "src/lib.rs/../mod.rs"
+        );
+
+        let absolute = pool.join(src, "/etc/hosts").unwrap();
+        assert_eq!(pool.render(&arena, absolute).unwrap(), "/etc/hosts");
+    }
+}
@@ -8,6 +8,7 @@ extern crate alloc;
 pub mod arena;
 pub mod draw;
 pub mod env;
+pub mod hash;
 pub mod intern;
 pub mod math;
 pub mod platform;
@@ -15,5 +16,5 @@ pub mod platform;
 pub use arena::{Arena, ArenaSlice};
 pub use draw::*;
 pub use env::*;
-pub use intern::StrPool;
+pub use intern::{GlobalPool, Interned, PathId, PathPool, StrPool, Symbol};
 pub use math::*;
@@ -1,34 +1,91 @@
-use crate::arena::{Arena, ArenaSlice, ArenaString};
-use crate::intern::StrPool;
-use alloc::collections::BTreeMap;
-use core::fmt::Write;
+use super::glob::{Glob, IgnoreRule};
+use super::walk::{WalkEntry, WalkOptions, Walker};
+use crate::arena::{AllocError, Arena, ArenaMap, ArenaSlice, ArenaString};
+use crate::intern::{Interned, PathId, PathPool};
+use alloc::collections::VecDeque;
+use alloc::string::String;
 use alloc::vec::Vec;
-use core::cell::{OnceCell, Ref, RefCell};
+use core::cell::{Cell, Ref, RefCell};
+use core::ffi::c_void;
+use core::fmt;
 use core::mem;
 use libc::{
-    close, closedir, fstat, open, opendir, read, readdir, stat, DT_DIR, DT_REG, S_IFBLK, S_IFCHR,
-    S_IFDIR, S_IFIFO, S_IFLNK, S_IFMT, S_IFREG, S_IFSOCK,
+    close, fstat, open, openat, pread, readlinkat, stat, S_IFBLK, S_IFCHR, S_IFDIR, S_IFIFO,
+    S_IFLNK, S_IFMT, S_IFREG, S_IFSOCK,
 };
-use libc::{write, O_CREAT, O_RDWR};
+use libc::{write, EINTR, ENOENT, ENOTDIR, O_CLOEXEC, O_CREAT, O_RDONLY, O_RDWR};
 
-pub struct Filesystem<'a> {
+// Later files take precedence, like a deeper .gitignore does.
+const IGNORE_FILES: [&str; 2] = [".gitignore", ".monolithignore"];
+
+pub struct Filesystem {
     arena: Arena,
-    root: ArenaString,
-    strings: StrPool,
-    nodes: RefCell<Vec<INode>>,
-    loaded: RefCell<BTreeMap<&'a str, i32>>,
+    root: PathId,
+    paths: PathPool,
+    tree: RefCell<Option<INode>>,
+    loaded: RefCell<ArenaMap<PathId, i32>>,
+}
+
+// Nodes only keep their path, names are resolved through the filesystem's path
+// pool so they live exactly as long as it does.
+pub struct INode {
+    pub path: PathId,
+    pub size: u64,
+    pub mtime: i64,
+    pub permissions: u32,
+    pub kind: INodeKind,
 }
 
-pub enum INode {
+pub enum INodeKind {
+    File,
     Directory(Vec<INode>),
-    File(ArenaString),
+    Symlink(PathId),
+    Other(FileType),
+}
+
+// Globs match paths relative to the scanned root. Directories that are
+// excluded or ignored are not descended into, includes only filter files.
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    pub include: Vec<Glob>,
+    pub exclude: Vec<Glob>,
+    pub hidden: bool,
+    pub max_depth: Option<usize>,
+    pub ignore_files: bool,
+}
+
+struct Scan<'a> {
+    arena: &'a Arena,
+    paths: &'a PathPool,
+    options: &'a ScanOptions,
+    names: Vec<&'a str>,
+    // Rules of every ignore file between the root and the current directory,
+    // with the depth of the directory they apply to.
+    rules: Vec<(usize, IgnoreRule)>,
+}
+
+pub struct DepthFirst<'a> {
+    stack: Vec<&'a INode>,
+}
+
+pub struct BreadthFirst<'a> {
+    queue: VecDeque<&'a INode>,
+}
+
+pub struct GlobMatches<'a> {
+    paths: &'a PathPool,
+    glob: &'a Glob,
+    stack: Vec<(&'a INode, usize)>,
+    names: Vec<&'a str>,
 }
 
 pub struct File {
     handle: i32,
-    stat: OnceCell<stat>,
+    path: String,
+    stat: Cell<Option<stat>>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FileType {
     BlockDevice,
     CharacterDevice,
@@ -40,103 +97,550 @@ pub enum FileType {
     Unknown,
 }
 
-fn cstr(arena: &Arena, text: &str) -> *mut i8 {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FsErrorKind {
+    NotFound,
+    PermissionDenied,
+    IsDirectory,
+    NotDirectory,
+    AlreadyExists,
+    NoSpace,
+    ReadOnly,
+    TooManyOpenFiles,
+    NameTooLong,
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FsError {
+    Alloc(AllocError),
+    Os {
+        kind: FsErrorKind,
+        errno: i32,
+        path: String,
+    },
+    InvalidUtf8 {
+        path: String,
+    },
+    Truncated {
+        path: String,
+        expected: usize,
+        actual: usize,
+    },
+    Loop {
+        path: String,
+    },
+}
+
+impl FsErrorKind {
+    pub fn from_errno(errno: i32) -> FsErrorKind {
+        match errno {
+            libc::ENOENT => FsErrorKind::NotFound,
+            libc::EACCES | libc::EPERM => FsErrorKind::PermissionDenied,
+            libc::EISDIR => FsErrorKind::IsDirectory,
+            libc::ENOTDIR => FsErrorKind::NotDirectory,
+            libc::EEXIST => FsErrorKind::AlreadyExists,
+            libc::ENOSPC | libc::EDQUOT => FsErrorKind::NoSpace,
+            libc::EROFS => FsErrorKind::ReadOnly,
+            libc::EMFILE | libc::ENFILE => FsErrorKind::TooManyOpenFiles,
+            libc::ENAMETOOLONG => FsErrorKind::NameTooLong,
+            _ => FsErrorKind::Other,
+        }
+    }
+}
+
+impl FsError {
+    pub(super) fn os(errno: i32, path: String) -> FsError {
+        FsError::Os {
+            kind: FsErrorKind::from_errno(errno),
+            errno,
+            path,
+        }
+    }
+
+    pub fn kind(&self) -> Option<FsErrorKind> {
+        match self {
+            FsError::Os { kind, .. } => Some(*kind),
+            _ => None,
+        }
+    }
+
+    pub fn errno(&self) -> Option<i32> {
+        match self {
+            FsError::Os { errno, .. } => Some(*errno),
+            _ => None,
+        }
+    }
+
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            FsError::Alloc(_) => None,
+            FsError::Os { path, .. }
+            | FsError::InvalidUtf8 { path }
+            | FsError::Loop { path }
+            | FsError::Truncated { path, .. } => Some(path),
+        }
+    }
+}
+
+impl From<AllocError> for FsError {
+    fn from(error: AllocError) -> Self {
+        FsError::Alloc(error)
+    }
+}
+
+impl fmt::Display for FsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FsError::Alloc(error) => write!(f, "{}", error),
+            FsError::Os { kind, errno, path } => {
+                write!(f, "{}: {:?} (errno {})", path, kind, errno)
+            }
+            FsError::InvalidUtf8 { path } => write!(f, "{}: not valid UTF-8", path),
+            FsError::Loop { path } => write!(f, "{}: filesystem loop", path),
+            FsError::Truncated {
+                path,
+                expected,
+                actual,
+            } => write!(f, "{}: expected {} bytes, read {}", path, expected, actual),
+        }
+    }
+}
+
+impl std::error::Error for FsError {}
+
+pub(super) fn errno() -> i32 {
+    std::io::Error::last_os_error().raw_os_error().unwrap_or(0)
+}
+
+pub(super) fn cstr(arena: &Arena, text: &str) -> Result<*mut i8, AllocError> {
     let len = text.len() + 1;
-    let mut string = arena.allocate::<i8>(len).unwrap();
+    let mut string = arena.allocate::<i8>(len)?;
 
     unsafe {
         core::ptr::copy_nonoverlapping(text.as_ptr() as *const i8, string.as_mut_ptr(), text.len());
     }
 
-    string.as_mut_ptr()
+    string[text.len()] = 0;
+
+    Ok(string.as_mut_ptr())
 }
 
-fn read_directory(arena: &Arena, path: &str) -> Vec<INode> {
-    let mut nodes = Vec::with_capacity(16);
+// Renders the path into the arena with a trailing nul.
+pub(super) fn cpath(arena: &Arena, paths: &PathPool, path: PathId) -> Result<*mut i8, AllocError> {
+    let path = paths.render(arena, path)?;
+    cstr(arena, &path)
+}
 
-    unsafe {
-        let dirp = opendir(cstr(arena, path));
-        let mut entry = readdir(dirp);
-
-        while !entry.is_null() {
-            let inner = *entry;
-            let name_cstr = core::ffi::CStr::from_ptr(inner.d_name.as_ptr());
-            let name = name_cstr.to_str().unwrap();
-
-            match name {
-                "." | ".." | ".git" => {}
-                _ => {
-                    let mut file_path = arena.allocate_string(path.len() + name.len() + 1).unwrap();
-                    let _ = write!(&mut file_path, "{}/{}", path, name);
-                    match inner.d_type {
-                        DT_DIR => {
-                            let inner_nodes = read_directory(arena, &file_path);
-                            nodes.push(INode::Directory(inner_nodes));
-                        }
-                        DT_REG => {
-                            nodes.push(INode::File(file_path));
-                        }
-                        _ => {}
-                    }
+pub(super) fn render(arena: &Arena, paths: &PathPool, path: PathId) -> Result<String, AllocError> {
+    Ok(String::from(paths.render(&arena.scratch(), path)?.as_str()))
+}
+
+pub(super) fn file_type(mode: libc::mode_t) -> FileType {
+    match mode & S_IFMT {
+        S_IFBLK => FileType::BlockDevice,
+        S_IFCHR => FileType::CharacterDevice,
+        S_IFDIR => FileType::Directory,
+        S_IFIFO => FileType::Pipe,
+        S_IFLNK => FileType::SymLink,
+        S_IFREG => FileType::Regular,
+        S_IFSOCK => FileType::Socket,
+        _ => FileType::Unknown,
+    }
+}
+
+// Read relative to the walker's directories, so links deeper than PATH_MAX
+// can still be read.
+fn read_link(
+    arena: &Arena,
+    paths: &PathPool,
+    walker: &Walker,
+    entry: &WalkEntry,
+) -> Result<PathId, FsError> {
+    let path = entry.path;
+    let scratch = arena.scratch();
+    let (dir, relative) = walker.locate(entry)?;
+    let link = cstr(&scratch, &relative)?;
+    let mut buf = scratch.allocate::<u8>(libc::PATH_MAX as usize)?;
+    let len = unsafe { readlinkat(dir, link, buf.as_mut_ptr() as *mut libc::c_char, buf.len()) };
+
+    if len < 0 {
+        let errno = errno();
+        return Err(FsError::os(errno, render(arena, paths, path)?));
+    }
+
+    match core::str::from_utf8(&buf[..len as usize]) {
+        Ok(target) => Ok(paths.path(target)?),
+        Err(_) => Err(FsError::InvalidUtf8 {
+            path: render(arena, paths, path)?,
+        }),
+    }
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        ScanOptions {
+            include: Vec::new(),
+            exclude: alloc::vec![Glob::new("**/.git").unwrap()],
+            hidden: true,
+            max_depth: None,
+            ignore_files: false,
+        }
+    }
+}
+
+impl<'a> Scan<'a> {
+    fn render_child(&self, path: PathId, name: &str) -> Result<String, AllocError> {
+        Ok(alloc::format!(
+            "{}/{}",
+            render(self.arena, self.paths, path)?,
+            name
+        ))
+    }
+
+    fn read_ignore_files(&mut self, walker: &Walker, entry: &WalkEntry) -> Result<(), FsError> {
+        if !self.options.ignore_files {
+            return Ok(());
+        }
+
+        let (dir, relative) = walker.locate(entry)?;
+
+        for name in IGNORE_FILES {
+            let relative = alloc::format!("{}/{}", relative, name);
+            let handle = unsafe {
+                openat(
+                    dir,
+                    cstr(&self.arena.scratch(), &relative)?,
+                    O_RDONLY | O_CLOEXEC,
+                )
+            };
+            let path = self.render_child(entry.path, name)?;
+
+            if handle < 0 {
+                let errno = errno();
+                if errno == ENOENT {
+                    continue;
                 }
+                return Err(FsError::os(errno, path));
             }
 
-            entry = readdir(dirp);
+            let file = File {
+                handle,
+                path,
+                stat: Cell::new(None),
+            };
+            let scratch = self.arena.scratch();
+            let text = file.read_to_string(&scratch);
+            unsafe { close(handle) };
+
+            let depth = self.names.len();
+            let rules = IgnoreRule::parse_all(&text?);
+            self.rules
+                .extend(rules.into_iter().map(|rule| (depth, rule)));
+        }
+
+        Ok(())
+    }
+
+    fn skipped(&self, is_dir: bool) -> bool {
+        let names = &self.names[..];
+
+        if self
+            .options
+            .exclude
+            .iter()
+            .any(|glob| glob.matches_components(names))
+        {
+            return true;
+        }
+
+        let ignored = self
+            .rules
+            .iter()
+            .rev()
+            .find_map(|(depth, rule)| rule.matches(&names[*depth..], is_dir))
+            .unwrap_or(false);
+
+        if ignored {
+            return true;
+        }
+
+        !is_dir
+            && !self.options.include.is_empty()
+            && !self
+                .options
+                .include
+                .iter()
+                .any(|glob| glob.matches_components(names))
+    }
+
+    fn descend(&mut self, walker: &mut Walker, entry: &WalkEntry) -> Result<(), FsError> {
+        if self.options.max_depth.is_some_and(|max| entry.depth >= max) {
+            walker.skip_subtree();
+            return Ok(());
         }
 
-        closedir(dirp);
+        self.read_ignore_files(walker, entry)
     }
 
-    nodes
+    // Builds the tree from a pre-order walk. Directories stay open on a stack
+    // until an entry outside of them comes along.
+    fn tree(&mut self, walker: &mut Walker) -> Result<INode, FsError> {
+        let mut open: Vec<INode> = Vec::new();
+
+        while let Some(entry) = walker.next() {
+            let entry = entry?;
+
+            close_until(&mut open, entry.depth.max(1));
+            self.names.truncate(entry.depth.saturating_sub(1));
+            self.rules.retain(|(depth, _)| *depth < entry.depth);
+
+            if entry.depth == 0 {
+                if !entry.is_dir() {
+                    let path = render(self.arena, self.paths, entry.path)?;
+                    return Err(FsError::os(ENOTDIR, path));
+                }
+
+                self.descend(walker, &entry)?;
+                open.push(INode::new(&entry, INodeKind::Directory(Vec::new())));
+                continue;
+            }
+
+            let name = self.paths.name(entry.path).as_str();
+
+            if !self.options.hidden && name.starts_with('.') {
+                if entry.is_dir() {
+                    walker.skip_subtree();
+                }
+                continue;
+            }
+
+            self.names.push(name);
+
+            if self.skipped(entry.is_dir()) {
+                if entry.is_dir() {
+                    walker.skip_subtree();
+                }
+                continue;
+            }
+
+            let kind = match entry.file_type {
+                FileType::Directory => {
+                    self.descend(walker, &entry)?;
+                    open.push(INode::new(&entry, INodeKind::Directory(Vec::new())));
+                    continue;
+                }
+                FileType::Regular => INodeKind::File,
+                FileType::SymLink => {
+                    INodeKind::Symlink(read_link(self.arena, self.paths, walker, &entry)?)
+                }
+                other => INodeKind::Other(other),
+            };
+
+            if let Some(INodeKind::Directory(children)) = open.last_mut().map(|dir| &mut dir.kind) {
+                children.push(INode::new(&entry, kind));
+            }
+        }
+
+        close_until(&mut open, 1);
+        Ok(open.pop().expect("the walk starts at the root"))
+    }
 }
 
-impl<'a> Filesystem<'a> {
-    pub fn new(root: &str) -> Self {
-        let arena = Arena::new(1024 * 1024);
-        let root = arena.push_string(root).unwrap();
+// Closes open directories until `len` remain, moving each into its parent.
+fn close_until(open: &mut Vec<INode>, len: usize) {
+    while open.len() > len {
+        let node = open.pop().unwrap();
 
-        Filesystem {
+        if let Some(INodeKind::Directory(children)) = open.last_mut().map(|dir| &mut dir.kind) {
+            children.push(node);
+        }
+    }
+}
+
+impl INode {
+    fn new(entry: &WalkEntry, kind: INodeKind) -> INode {
+        INode {
+            path: entry.path,
+            size: entry.size,
+            mtime: entry.mtime,
+            permissions: entry.permissions,
+            kind,
+        }
+    }
+
+    pub fn name<'a>(&self, paths: &'a PathPool) -> Interned<'a> {
+        paths.name(self.path)
+    }
+
+    pub fn is_file(&self) -> bool {
+        matches!(self.kind, INodeKind::File)
+    }
+
+    pub fn is_dir(&self) -> bool {
+        matches!(self.kind, INodeKind::Directory(_))
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        matches!(self.kind, INodeKind::Symlink(_))
+    }
+
+    pub fn children(&self) -> &[INode] {
+        match &self.kind {
+            INodeKind::Directory(children) => children,
+            _ => &[],
+        }
+    }
+
+    // Looks a '/' separated path up relative to this node.
+    pub fn find(&self, paths: &PathPool, path: &str) -> Option<&INode> {
+        path.split('/')
+            .filter(|name| !name.is_empty() && *name != ".")
+            .try_fold(self, |node, name| {
+                node.children()
+                    .iter()
+                    .find(|child| child.name(paths) == name)
+            })
+    }
+
+    pub fn depth_first(&self) -> DepthFirst<'_> {
+        DepthFirst {
+            stack: alloc::vec![self],
+        }
+    }
+
+    pub fn breadth_first(&self) -> BreadthFirst<'_> {
+        BreadthFirst {
+            queue: VecDeque::from([self]),
+        }
+    }
+
+    // Matches descendants by their path relative to this node.
+    pub fn glob<'a>(&'a self, paths: &'a PathPool, glob: &'a Glob) -> GlobMatches<'a> {
+        GlobMatches {
+            paths,
+            glob,
+            stack: self
+                .children()
+                .iter()
+                .rev()
+                .map(|child| (child, 0))
+                .collect(),
+            names: Vec::new(),
+        }
+    }
+}
+
+impl<'a> Iterator for DepthFirst<'a> {
+    type Item = &'a INode;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.stack.extend(node.children().iter().rev());
+        Some(node)
+    }
+}
+
+impl<'a> Iterator for BreadthFirst<'a> {
+    type Item = &'a INode;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.queue.pop_front()?;
+        self.queue.extend(node.children());
+        Some(node)
+    }
+}
+
+impl<'a> Iterator for GlobMatches<'a> {
+    type Item = &'a INode;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((node, depth)) = self.stack.pop() {
+            self.names.truncate(depth);
+            self.names.push(node.name(self.paths).as_str());
+            self.stack
+                .extend(node.children().iter().rev().map(|child| (child, depth + 1)));
+
+            if self.glob.matches_components(&self.names) {
+                return Some(node);
+            }
+        }
+
+        None
+    }
+}
+
+impl Filesystem {
+    pub fn new(root: &str) -> Result<Self, AllocError> {
+        let arena = Arena::growable(1024 * 1024);
+        let paths = PathPool::new(1024 * 10);
+        let root = paths.path(root)?;
+
+        Ok(Filesystem {
             arena,
             root,
-            nodes: RefCell::new(Vec::new()),
-            strings: StrPool::new(1024 * 10),
-            loaded: RefCell::new(BTreeMap::new()),
-        }
+            tree: RefCell::new(None),
+            paths,
+            loaded: RefCell::new(ArenaMap::new()),
+        })
     }
 
-    pub fn read(&self) -> Ref<Vec<INode>> {
-        let nodes = read_directory(&self.arena, &self.root);
-        let _ = self.nodes.replace(nodes);
-        self.nodes.borrow()
+    pub fn read(&self, options: &ScanOptions) -> Result<Ref<'_, INode>, FsError> {
+        let mut scan = Scan {
+            arena: &self.arena,
+            paths: &self.paths,
+            options,
+            names: Vec::new(),
+            rules: Vec::new(),
+        };
+        let root = scan.tree(&mut self.walk(WalkOptions::default()))?;
+
+        let _ = self.tree.replace(Some(root));
+        Ok(Ref::map(self.tree.borrow(), |tree| tree.as_ref().unwrap()))
+    }
+
+    pub fn walk(&self, options: WalkOptions) -> Walker<'_> {
+        Walker::new(&self.arena, &self.paths, self.root, options)
     }
 
-    pub fn load(&self, path: &'a str) -> File {
+    pub fn load(&self, path: &str) -> Result<File, FsError> {
         let mut loaded = self.loaded.borrow_mut();
-        let path = self.strings.intern(path).unwrap();
-        let entry = loaded.get(&path);
+        let id = self.paths.path(path)?;
+        let entry = loaded.get(&id);
 
         match entry {
             None => {
-                let cpath = cstr(&self.arena, path);
-                let handle = unsafe { open(cpath, O_RDWR | O_CREAT) };
+                let cpath = cpath(&self.arena.scratch(), &self.paths, id)?;
+                let handle = unsafe { open(cpath, O_RDWR | O_CREAT, 0o644) };
 
-                loaded.insert(path, handle);
+                if handle < 0 {
+                    return Err(FsError::os(errno(), String::from(path)));
+                }
 
-                File {
-                    handle,
-                    stat: OnceCell::new(),
+                if let Err(error) = loaded.insert(&self.arena, id, handle) {
+                    unsafe { close(handle) };
+                    return Err(error.into());
                 }
+
+                Ok(File {
+                    handle,
+                    path: String::from(path),
+                    stat: Cell::new(None),
+                })
             }
-            Some(handle) => File {
+            Some(handle) => Ok(File {
                 handle: *handle,
-                stat: OnceCell::new(),
-            },
+                path: String::from(path),
+                stat: Cell::new(None),
+            }),
         }
     }
 
-    pub fn unload(&self, path: &'a str) {
+    pub fn unload(&self, path: &str) {
         let mut loaded = self.loaded.borrow_mut();
-        let path = self.strings.intern(path).unwrap();
+        let Some(path) = self.paths.get(path) else {
+            return;
+        };
         let entry = loaded.get(&path);
 
         match entry {
@@ -148,12 +652,24 @@ impl<'a> Filesystem<'a> {
         }
     }
 
-    pub fn loaded(&self) -> Ref<BTreeMap<&'a str, i32>> {
-        self.loaded.borrow()
+    pub fn root(&self) -> PathId {
+        self.root
+    }
+
+    pub fn paths(&self) -> &PathPool {
+        &self.paths
+    }
+
+    pub fn loaded(&self) -> Vec<(PathId, i32)> {
+        self.loaded
+            .borrow()
+            .iter()
+            .map(|(path, handle)| (*path, *handle))
+            .collect()
     }
 }
 
-impl Drop for Filesystem<'_> {
+impl Drop for Filesystem {
     fn drop(&mut self) {
         for (_path, desc) in self.loaded.borrow().iter() {
             unsafe {
@@ -164,88 +680,351 @@ impl Drop for Filesystem<'_> {
 }
 
 impl File {
-    pub fn stat(&self) -> stat {
-        match self.stat.get() {
-            None => {
-                let stat = unsafe {
-                    let mut data: stat = mem::zeroed();
-                    fstat(self.handle, &mut data);
-                    data
-                };
-
-                let _ = self.stat.set(stat);
-                self.stat()
-            }
-            Some(stat) => *stat,
+    pub fn handle(&self) -> i32 {
+        self.handle
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn stat(&self) -> Result<stat, FsError> {
+        if let Some(stat) = self.stat.get() {
+            return Ok(stat);
+        }
+
+        let mut data: stat = unsafe { mem::zeroed() };
+
+        if unsafe { fstat(self.handle, &mut data) } < 0 {
+            return Err(FsError::os(errno(), self.path.clone()));
         }
+
+        self.stat.set(Some(data));
+        Ok(data)
     }
 
-    pub fn size(&self) -> i64 {
-        match self.stat.get() {
-            None => {
-                self.stat();
-                self.size()
+    pub fn size(&self) -> Result<i64, FsError> {
+        Ok(self.stat()?.st_size)
+    }
+
+    pub fn blocks(&self) -> Result<i64, FsError> {
+        Ok(self.stat()?.st_blocks)
+    }
+
+    pub fn file_type(&self) -> Result<FileType, FsError> {
+        Ok(file_type(self.stat()?.st_mode))
+    }
+
+    // Reads the whole file from the start, independent of the shared handle's
+    // current offset.
+    pub fn read(&self, arena: &Arena) -> Result<ArenaSlice<u8>, FsError> {
+        let size = self.size()? as usize;
+        let mut buf = arena.allocate::<u8>(size)?;
+        let mut offset = 0;
+
+        while offset < size {
+            let count = unsafe {
+                pread(
+                    self.handle,
+                    buf.as_mut_ptr().add(offset) as *mut c_void,
+                    size - offset,
+                    offset as libc::off_t,
+                )
+            };
+
+            if count < 0 {
+                let errno = errno();
+                if errno == EINTR {
+                    continue;
+                }
+                return Err(FsError::os(errno, self.path.clone()));
             }
-            Some(stat) => stat.st_size,
+
+            if count == 0 {
+                return Err(FsError::Truncated {
+                    path: self.path.clone(),
+                    expected: size,
+                    actual: offset,
+                });
+            }
+
+            offset += count as usize;
         }
+
+        Ok(buf)
     }
 
-    pub fn blocks(&self) -> i64 {
-        match self.stat.get() {
-            None => {
-                self.stat();
-                self.blocks()
-            }
-            Some(stat) => stat.st_blocks,
+    pub fn read_to_string(&self, arena: &Arena) -> Result<ArenaString, FsError> {
+        let inner = self.read(arena)?;
+
+        if core::str::from_utf8(&inner).is_err() {
+            return Err(FsError::InvalidUtf8 {
+                path: self.path.clone(),
+            });
         }
+
+        Ok(ArenaString::from_slice(inner))
     }
 
-    pub fn file_type(&self) -> FileType {
-        match self.stat.get() {
-            None => {
-                self.stat();
-                self.file_type()
+    pub fn append(&self, mut data: &[u8]) -> Result<(), FsError> {
+        while !data.is_empty() {
+            let written = unsafe { write(self.handle, data.as_ptr() as *const c_void, data.len()) };
+
+            if written < 0 {
+                let errno = errno();
+                if errno == EINTR {
+                    continue;
+                }
+                return Err(FsError::os(errno, self.path.clone()));
             }
-            Some(stat) => match stat.st_mode & S_IFMT {
-                S_IFBLK => FileType::BlockDevice,
-                S_IFCHR => FileType::CharacterDevice,
-                S_IFDIR => FileType::Directory,
-                S_IFIFO => FileType::Pipe,
-                S_IFLNK => FileType::SymLink,
-                S_IFREG => FileType::Regular,
-                S_IFSOCK => FileType::Socket,
-                _ => FileType::Unknown,
-            },
+
+            data = &data[written as usize..];
         }
+
+        // The cached size no longer matches the file.
+        self.stat.set(None);
+        Ok(())
     }
+}
 
-    pub fn read(&self, arena: &Arena) -> ArenaSlice<u8> {
-        let size = self.size();
-        let mut buf = arena.allocate::<u8>(size as usize).unwrap();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        unsafe {
-            read(
-                self.handle,
-                buf.as_mut_ptr() as *mut core::ffi::c_void,
-                size as usize,
-            );
-        }
+    fn temp_path(name: &str) -> String {
+        let dir = std::env::temp_dir();
+        let path = dir.join(alloc::format!("monolith-{}-{}", name, std::process::id()));
+        String::from(path.to_str().unwrap())
+    }
+
+    #[test]
+    fn test_filesystem_errors() {
+        let missing = temp_path("missing");
+        let filesystem = Filesystem::new(&missing).unwrap();
+
+        let error = filesystem.read(&ScanOptions::default()).err().unwrap();
+        assert_eq!(error.kind(), Some(FsErrorKind::NotFound));
+        assert_eq!(error.path(), Some(missing.as_str()));
 
-        buf
+        let nested = alloc::format!("{}/asset.bin", missing);
+        let error = filesystem.load(&nested).err().unwrap();
+        assert_eq!(error.kind(), Some(FsErrorKind::NotFound));
+        assert_eq!(error.path(), Some(nested.as_str()));
+        assert!(filesystem.loaded().is_empty());
+
+        let dir = std::env::temp_dir();
+        let error = filesystem.load(dir.to_str().unwrap()).err().unwrap();
+        assert_eq!(error.kind(), Some(FsErrorKind::IsDirectory));
     }
 
-    pub fn read_to_string(&self, arena: &Arena) -> ArenaString {
-        let inner = self.read(arena);
-        ArenaString::from_slice(inner)
+    #[test]
+    fn test_filesystem_tree() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = temp_path("tree");
+        std::fs::create_dir_all(alloc::format!("{}/assets/textures", root)).unwrap();
+        std::fs::create_dir_all(alloc::format!("{}/assets/models/props", root)).unwrap();
+        std::fs::write(
+            alloc::format!("{}/assets/textures/wall.png", root),
+            [0u8; 12],
+        )
+        .unwrap();
+        std::fs::write(alloc::format!("{}/assets/models/cube.obj", root), "v").unwrap();
+        std::fs::write(
+            alloc::format!("{}/assets/models/props/crate.obj", root),
+            "v",
+        )
+        .unwrap();
+        std::fs::write(alloc::format!("{}/readme.txt", root), "").unwrap();
+        std::os::unix::fs::symlink("assets/models", alloc::format!("{}/models", root)).unwrap();
+        std::fs::set_permissions(
+            alloc::format!("{}/readme.txt", root),
+            std::fs::Permissions::from_mode(0o600),
+        )
+        .unwrap();
+
+        let filesystem = Filesystem::new(&root).unwrap();
+        let tree = filesystem.read(&ScanOptions::default()).unwrap();
+        let paths = filesystem.paths();
+        let arena = Arena::new(1024);
+
+        assert!(tree.is_dir());
+        assert_eq!(tree.children().len(), 3);
+
+        let wall = tree.find(paths, "assets/textures/wall.png").unwrap();
+        assert_eq!(wall.name(paths), "wall.png");
+        assert_eq!(wall.size, 12);
+        assert!(wall.is_file());
+        assert!(wall.mtime > 0);
+        assert_eq!(
+            paths.render(&arena, wall.path).unwrap().as_str(),
+            alloc::format!("{}/assets/textures/wall.png", root)
+        );
+        assert!(tree.find(paths, "assets/missing.png").is_none());
+        assert_eq!(tree.find(paths, "readme.txt").unwrap().permissions, 0o600);
+
+        let link = tree.find(paths, "models").unwrap();
+        match link.kind {
+            INodeKind::Symlink(target) => {
+                assert_eq!(paths.render(&arena, target).unwrap(), "assets/models");
+            }
+            _ => panic!("expected a symlink"),
+        }
+        assert!(link.children().is_empty());
+
+        let depth_first: Vec<_> = tree
+            .depth_first()
+            .map(|node| node.name(paths).as_str())
+            .collect();
+        assert_eq!(depth_first.len(), 10);
+        let position = |name| depth_first.iter().position(|node| *node == name).unwrap();
+        assert_eq!(depth_first[0], tree.name(paths).as_str());
+        assert_eq!(position("wall.png"), position("textures") + 1);
+        assert_eq!(position("crate.obj"), position("props") + 1);
+
+        let breadth_first: Vec<_> = tree
+            .breadth_first()
+            .map(|node| node.name(paths).as_str())
+            .collect();
+        assert_eq!(breadth_first.len(), 10);
+        let mut top = breadth_first[1..4].to_vec();
+        top.sort();
+        assert_eq!(top, ["assets", "models", "readme.txt"]);
+        assert_eq!(breadth_first.last(), Some(&"crate.obj"));
+
+        let glob = Glob::new("**/*.obj").unwrap();
+        let mut objects: Vec<_> = tree
+            .glob(paths, &glob)
+            .map(|node| node.name(paths).as_str())
+            .collect();
+        objects.sort();
+        assert_eq!(objects, ["crate.obj", "cube.obj"]);
+
+        let glob = Glob::new("assets/*").unwrap();
+        assert_eq!(tree.glob(paths, &glob).count(), 2);
+
+        drop(tree);
+        std::fs::remove_dir_all(&root).unwrap();
     }
 
-    pub fn append(&self, data: &[u8]) {
-        unsafe {
-            write(
-                self.handle,
-                data.as_ptr() as *const core::ffi::c_void,
-                data.len(),
-            );
+    #[test]
+    fn test_filesystem_scan_options() {
+        let root = temp_path("scan");
+        for (path, contents) in [
+            (".gitignore", "target/\n*.log\n!keep.log\n"),
+            ("target/out.bin", ""),
+            ("src/main.rs", ""),
+            ("src/.hidden", ""),
+            ("src/debug.log", ""),
+            ("src/keep.log", ""),
+            ("src/.monolithignore", "generated/\n"),
+            ("src/generated/mesh.rs", ""),
+            ("src/target", ""),
+            ("assets/big/blob.bin", ""),
+            ("assets/wall.png", ""),
+        ] {
+            let path = std::path::Path::new(&root).join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
         }
+
+        let filesystem = Filesystem::new(&root).unwrap();
+        let scan = |options: &ScanOptions| {
+            let tree = filesystem.read(options).unwrap();
+            let mut files: Vec<String> = Vec::new();
+            let mut stack = alloc::vec![(&*tree, String::new())];
+
+            while let Some((node, prefix)) = stack.pop() {
+                for child in node.children() {
+                    let path = alloc::format!("{}{}", prefix, child.name(filesystem.paths()));
+                    if child.is_dir() {
+                        stack.push((child, alloc::format!("{}/", path)));
+                    } else {
+                        files.push(path);
+                    }
+                }
+            }
+
+            files.sort();
+            files
+        };
+
+        assert_eq!(scan(&ScanOptions::default()).len(), 11);
+
+        let files = scan(&ScanOptions {
+            ignore_files: true,
+            ..Default::default()
+        });
+        assert_eq!(
+            files,
+            [
+                ".gitignore",
+                "assets/big/blob.bin",
+                "assets/wall.png",
+                "src/.hidden",
+                "src/.monolithignore",
+                "src/keep.log",
+                "src/main.rs",
+                "src/target",
+            ]
+        );
+
+        let files = scan(&ScanOptions {
+            include: alloc::vec![Glob::new("**/*.rs").unwrap()],
+            exclude: alloc::vec![Glob::new("src/generated").unwrap()],
+            hidden: false,
+            ..Default::default()
+        });
+        assert_eq!(files, ["src/main.rs"]);
+
+        let files = scan(&ScanOptions {
+            exclude: alloc::vec![Glob::new("assets/big").unwrap(), Glob::new("src").unwrap()],
+            hidden: false,
+            ..Default::default()
+        });
+        assert_eq!(files, ["assets/wall.png", "target/out.bin"]);
+
+        let options = ScanOptions {
+            max_depth: Some(1),
+            ..Default::default()
+        };
+        let tree = filesystem.read(&options).unwrap();
+        assert_eq!(tree.children().len(), 4);
+        assert!(tree
+            .find(filesystem.paths(), "src")
+            .unwrap()
+            .children()
+            .is_empty());
+        drop(tree);
+
+        let options = ScanOptions {
+            max_depth: Some(0),
+            ..Default::default()
+        };
+        assert!(filesystem.read(&options).unwrap().children().is_empty());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_file_read_append() {
+        let path = temp_path("append");
+        let filesystem = Filesystem::new(".").unwrap();
+        let arena = Arena::new(1024);
+
+        let file = filesystem.load(&path).unwrap();
+        file.append(b"hello ").unwrap();
+        assert_eq!(file.size().unwrap(), 6);
+        file.append(b"world").unwrap();
+
+        assert_eq!(file.read_to_string(&arena).unwrap(), "hello world");
+        assert_eq!(&file.read(&arena).unwrap()[..], b"hello world");
+
+        file.append(&[0xff, 0xfe]).unwrap();
+        let error = file.read_to_string(&arena).err().unwrap();
+        assert_eq!(error, FsError::InvalidUtf8 { path: path.clone() });
+
+        filesystem.unload(&path);
+        std::fs::remove_file(&path).unwrap();
     }
 }
@@ -0,0 +1,347 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Char(char),
+    Any,
+    Star,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Recursive,
+    Tokens(Vec<Token>),
+}
+
+// A glob over '/' separated paths. `*`, `?` and `[a-z]` classes stay within a
+// component, a `**` component matches any number of components.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glob {
+    segments: Vec<Segment>,
+}
+
+// One line of a .gitignore style file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IgnoreRule {
+    glob: Glob,
+    negated: bool,
+    directory_only: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GlobError {
+    UnclosedClass,
+    TrailingEscape,
+}
+
+impl fmt::Display for GlobError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GlobError::UnclosedClass => write!(f, "glob has an unclosed character class"),
+            GlobError::TrailingEscape => write!(f, "glob ends with an escape"),
+        }
+    }
+}
+
+impl std::error::Error for GlobError {}
+
+impl Token {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Token::Char(expected) => *expected == c,
+            Token::Any => true,
+            Token::Star => false,
+            Token::Class { negated, ranges } => {
+                ranges.iter().any(|(lo, hi)| (*lo..=*hi).contains(&c)) != *negated
+            }
+        }
+    }
+}
+
+fn parse_class(chars: &mut core::iter::Peekable<core::str::Chars>) -> Result<Token, GlobError> {
+    let negated = matches!(chars.peek(), Some('!' | '^'));
+    if negated {
+        chars.next();
+    }
+
+    let mut ranges = Vec::new();
+    let mut first = true;
+
+    loop {
+        let lo = match chars.next() {
+            None => return Err(GlobError::UnclosedClass),
+            Some(']') if !first => break,
+            Some('\\') => chars.next().ok_or(GlobError::TrailingEscape)?,
+            Some(c) => c,
+        };
+        first = false;
+
+        let mut ahead = chars.clone();
+        let hi = match (ahead.next(), ahead.next()) {
+            (Some('-'), Some(hi)) if hi != ']' => {
+                chars.next();
+                chars.next();
+                hi
+            }
+            _ => lo,
+        };
+
+        ranges.push((lo, hi));
+    }
+
+    Ok(Token::Class { negated, ranges })
+}
+
+fn parse_segment(segment: &str) -> Result<Segment, GlobError> {
+    if segment == "**" {
+        return Ok(Segment::Recursive);
+    }
+
+    let mut tokens = Vec::new();
+    let mut chars = segment.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let token = match c {
+            '*' if tokens.last() == Some(&Token::Star) => continue,
+            '*' => Token::Star,
+            '?' => Token::Any,
+            '[' => parse_class(&mut chars)?,
+            '\\' => Token::Char(chars.next().ok_or(GlobError::TrailingEscape)?),
+            c => Token::Char(c),
+        };
+
+        tokens.push(token);
+    }
+
+    Ok(Segment::Tokens(tokens))
+}
+
+fn match_tokens(tokens: &[Token], name: &str) -> bool {
+    let text: Vec<char> = name.chars().collect();
+    let mut t = 0;
+    let mut p = 0;
+    // The last star seen and the text position it is currently matched up to.
+    let mut star = None;
+
+    while t < text.len() {
+        match tokens.get(p) {
+            Some(Token::Star) => {
+                star = Some((p, t));
+                p += 1;
+                continue;
+            }
+            Some(token) if token.matches(text[t]) => {
+                p += 1;
+                t += 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        match star {
+            Some((star_p, star_t)) => {
+                p = star_p + 1;
+                t = star_t + 1;
+                star = Some((star_p, star_t + 1));
+            }
+            None => return false,
+        }
+    }
+
+    tokens[p..].iter().all(|token| *token == Token::Star)
+}
+
+fn match_segments(segments: &[Segment], names: &[&str]) -> bool {
+    match segments.split_first() {
+        None => names.is_empty(),
+        // A trailing `**` matches everything inside, but not the directory itself.
+        Some((Segment::Recursive, [])) => !names.is_empty(),
+        Some((Segment::Recursive, rest)) => {
+            (0..=names.len()).any(|skip| match_segments(rest, &names[skip..]))
+        }
+        Some((Segment::Tokens(tokens), rest)) => match names.split_first() {
+            Some((name, names)) => match_tokens(tokens, name) && match_segments(rest, names),
+            None => false,
+        },
+    }
+}
+
+impl Glob {
+    pub fn new(pattern: &str) -> Result<Glob, GlobError> {
+        let segments = pattern
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(parse_segment)
+            .collect::<Result<_, _>>()?;
+
+        Ok(Glob { segments })
+    }
+
+    pub fn matches(&self, path: &str) -> bool {
+        let names: Vec<&str> = path.split('/').filter(|name| !name.is_empty()).collect();
+        self.matches_components(&names)
+    }
+
+    pub fn matches_components(&self, names: &[&str]) -> bool {
+        match_segments(&self.segments, names)
+    }
+}
+
+impl IgnoreRule {
+    // Returns None for blank lines, comments and patterns that don't parse.
+    pub fn parse(line: &str) -> Option<IgnoreRule> {
+        let line = line.trim_end_matches('\r');
+        let trimmed = line.trim_end_matches(' ');
+
+        // A trailing space survives when it is escaped.
+        let mut pattern = if trimmed.ends_with('\\') && trimmed.len() < line.len() {
+            &line[..trimmed.len() + 1]
+        } else {
+            trimmed
+        };
+
+        if pattern.is_empty() || pattern.starts_with('#') {
+            return None;
+        }
+
+        let negated = pattern.starts_with('!');
+        if negated {
+            pattern = &pattern[1..];
+        }
+
+        let directory_only = pattern.ends_with('/');
+        let pattern = pattern.trim_end_matches('/');
+
+        if pattern.is_empty() {
+            return None;
+        }
+
+        // Patterns with a slash before the end are relative to the ignore file,
+        // the others match at any depth below it.
+        let glob = if pattern.contains('/') {
+            Glob::new(pattern.trim_start_matches('/'))
+        } else {
+            Glob::new(&alloc::format!("**/{}", pattern))
+        };
+
+        Some(IgnoreRule {
+            glob: glob.ok()?,
+            negated,
+            directory_only,
+        })
+    }
+
+    pub fn parse_all(text: &str) -> Vec<IgnoreRule> {
+        text.lines().filter_map(IgnoreRule::parse).collect()
+    }
+
+    pub fn is_negated(&self) -> bool {
+        self.negated
+    }
+
+    pub fn is_directory_only(&self) -> bool {
+        self.directory_only
+    }
+
+    // Some(true) if the rule ignores the path, Some(false) if it re-includes it
+    // and None if it doesn't apply. The names are relative to the ignore file.
+    pub fn matches(&self, names: &[&str], is_dir: bool) -> Option<bool> {
+        if self.directory_only && !is_dir {
+            return None;
+        }
+
+        self.glob.matches_components(names).then_some(!self.negated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob() {
+        let glob = Glob::new("**/*.obj").unwrap();
+        assert!(glob.matches("cube.obj"));
+        assert!(glob.matches("assets/models/cube.obj"));
+        assert!(!glob.matches("assets/models/cube.mtl"));
+        assert!(!glob.matches("assets/cube.obj/readme"));
+
+        let glob = Glob::new("assets/*/wall.png").unwrap();
+        assert!(glob.matches("assets/textures/wall.png"));
+        assert!(!glob.matches("assets/wall.png"));
+        assert!(!glob.matches("assets/a/b/wall.png"));
+
+        let glob = Glob::new("src/**/mod.rs").unwrap();
+        assert!(glob.matches("src/mod.rs"));
+        assert!(glob.matches("src/arena/mod.rs"));
+        assert!(!glob.matches("tests/arena/mod.rs"));
+
+        let glob = Glob::new("src/**").unwrap();
+        assert!(glob.matches("src/main.rs"));
+        assert!(glob.matches("src/arena/mod.rs"));
+        assert!(!glob.matches("src"));
+
+        let glob = Glob::new("level?_[0-9a-c].*").unwrap();
+        assert!(glob.matches("level1_b.bin"));
+        assert!(!glob.matches("level1_d.bin"));
+        assert!(!glob.matches("level12_b.bin"));
+
+        let glob = Glob::new("[!.]*\\*").unwrap();
+        assert!(glob.matches("name*"));
+        assert!(!glob.matches(".name*"));
+        assert!(!glob.matches("name"));
+
+        assert_eq!(Glob::new("[abc"), Err(GlobError::UnclosedClass));
+        assert_eq!(Glob::new("abc\\"), Err(GlobError::TrailingEscape));
+    }
+
+    #[test]
+    fn test_ignore_rules() {
+        let rules = IgnoreRule::parse_all(
+            "# build output\n\ntarget/\n*.swp\n!keep.swp\n/docs/*.html\n\\#notes\ntrailing\\ \n",
+        );
+        assert_eq!(rules.len(), 6);
+
+        let ignored = |names: &[&str], is_dir: bool| {
+            rules
+                .iter()
+                .rev()
+                .find_map(|rule| rule.matches(names, is_dir))
+                .unwrap_or(false)
+        };
+
+        assert!(ignored(&["target"], true));
+        assert!(ignored(&["crates", "target"], true));
+        assert!(!ignored(&["target"], false));
+        assert!(ignored(&["src", ".main.rs.swp"], false));
+        assert!(!ignored(&["src", "keep.swp"], false));
+        assert!(ignored(&["docs", "index.html"], false));
+        assert!(!ignored(&["src", "docs", "index.html"], false));
+        assert!(ignored(&["#notes"], false));
+        assert!(ignored(&["trailing "], false));
+        assert!(!ignored(&["trailing"], false));
+
+        assert!(rules[2].is_negated());
+        assert!(rules[0].is_directory_only());
+        assert_eq!(IgnoreRule::parse("   "), None);
+
+        let rules = IgnoreRule::parse_all("build/**\n!build/keep\n");
+        let ignored = |names: &[&str], is_dir: bool| {
+            rules
+                .iter()
+                .rev()
+                .find_map(|rule| rule.matches(names, is_dir))
+                .unwrap_or(false)
+        };
+
+        assert!(!ignored(&["build"], true));
+        assert!(ignored(&["build", "x"], false));
+        assert!(ignored(&["build", "x", "y"], false));
+        assert!(!ignored(&["build", "keep"], false));
+    }
+}
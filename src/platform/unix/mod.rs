@@ -1 +1,3 @@
 pub mod filesystem;
+pub mod glob;
+pub mod walk;
@@ -0,0 +1,782 @@
+use super::filesystem::{cpath, cstr, errno, file_type, render, FileType, FsError};
+use crate::arena::{AllocError, Arena};
+use crate::intern::{Interned, PathId, PathPool};
+use alloc::collections::VecDeque;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::mem;
+use libc::{
+    close, closedir, dirent, dirfd, fdopendir, fstat, fstatat, openat, readdir, stat, AT_FDCWD,
+    AT_SYMLINK_NOFOLLOW, DIR, ENOENT, O_CLOEXEC, O_DIRECTORY, O_NOFOLLOW, O_RDONLY,
+};
+
+// nftw from <ftw.h> walks a tree too, but it drives a callback until the whole
+// walk is done. An iterator has to stop between entries and skip subtrees on
+// request, so the walker keeps its own stack of directories instead. Children
+// are opened relative to their parent, so paths never grow past one name.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WalkOrder {
+    #[default]
+    PreOrder,
+    PostOrder,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WalkOptions {
+    pub follow_symlinks: bool,
+    pub same_filesystem: bool,
+    pub order: WalkOrder,
+    // Directories held open at once, like nftw's nopenfd. Going deeper reads
+    // the rest of the parent ahead and closes it.
+    pub max_open_dirs: usize,
+}
+
+// With `follow_symlinks` the metadata is the link target's and `followed` is
+// set, dangling links are reported as links.
+#[derive(Debug, Clone, Copy)]
+pub struct WalkEntry {
+    pub path: PathId,
+    pub depth: usize,
+    pub file_type: FileType,
+    pub followed: bool,
+    pub size: u64,
+    pub mtime: i64,
+    pub permissions: u32,
+    pub device: libc::dev_t,
+    pub inode: libc::ino_t,
+}
+
+struct Frame {
+    // Null while the directory is closed to stay under `max_open_dirs`.
+    dirp: *mut DIR,
+    entry: WalkEntry,
+    skip: bool,
+    // The rest of the directory, read ahead when it was closed. A reopened
+    // directory is only used to open the children from here.
+    buffered: Option<VecDeque<Result<WalkEntry, FsError>>>,
+}
+
+pub struct Walker<'a> {
+    arena: &'a Arena,
+    paths: &'a PathPool,
+    options: WalkOptions,
+    root: Option<PathId>,
+    device: libc::dev_t,
+    stack: Vec<Frame>,
+    // A directory yielded in pre-order, entered on the next call unless the
+    // caller skips it first.
+    pending: Option<WalkEntry>,
+    ready: VecDeque<Result<WalkEntry, FsError>>,
+}
+
+impl Default for WalkOptions {
+    fn default() -> Self {
+        WalkOptions {
+            follow_symlinks: false,
+            same_filesystem: false,
+            order: WalkOrder::PreOrder,
+            max_open_dirs: 32,
+        }
+    }
+}
+
+impl WalkEntry {
+    fn new(path: PathId, depth: usize, data: &stat, followed: bool) -> WalkEntry {
+        WalkEntry {
+            path,
+            depth,
+            file_type: file_type(data.st_mode),
+            followed,
+            size: data.st_size as u64,
+            mtime: data.st_mtime,
+            permissions: data.st_mode & 0o7777,
+            device: data.st_dev,
+            inode: data.st_ino,
+        }
+    }
+
+    pub fn name<'a>(&self, paths: &'a PathPool) -> Interned<'a> {
+        paths.name(self.path)
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.file_type == FileType::Directory
+    }
+}
+
+impl Frame {
+    fn has_subdirectories(&self) -> bool {
+        !self.skip
+            && self.buffered.as_ref().is_some_and(|buffered| {
+                buffered
+                    .iter()
+                    .any(|item| matches!(item, Ok(entry) if entry.is_dir()))
+            })
+    }
+}
+
+impl<'a> Walker<'a> {
+    pub(super) fn new(
+        arena: &'a Arena,
+        paths: &'a PathPool,
+        root: PathId,
+        options: WalkOptions,
+    ) -> Walker<'a> {
+        Walker {
+            arena,
+            paths,
+            options,
+            root: Some(root),
+            device: 0,
+            stack: Vec::new(),
+            pending: None,
+            ready: VecDeque::new(),
+        }
+    }
+
+    // Skips the contents of the directory that was just yielded in pre-order.
+    // Other entries have no contents left to skip.
+    pub fn skip_subtree(&mut self) {
+        self.pending = None;
+    }
+
+    // Skips the rest of the directory the last entry was read from.
+    pub fn skip_siblings(&mut self) {
+        if let Some(frame) = self.stack.last_mut() {
+            frame.skip = true;
+        }
+    }
+
+    fn root(&mut self, path: PathId) -> Result<WalkEntry, FsError> {
+        let mut data: stat = unsafe { mem::zeroed() };
+
+        if unsafe { stat(cpath(&self.arena.scratch(), self.paths, path)?, &mut data) } < 0 {
+            let errno = errno();
+            return Err(FsError::os(errno, render(self.arena, self.paths, path)?));
+        }
+
+        self.device = data.st_dev;
+        Ok(WalkEntry::new(path, 0, &data, false))
+    }
+
+    fn child(
+        &self,
+        parent: &WalkEntry,
+        dir: i32,
+        entry: &dirent,
+    ) -> Result<Option<WalkEntry>, FsError> {
+        let name_cstr = unsafe { core::ffi::CStr::from_ptr(entry.d_name.as_ptr()) };
+        let Ok(name) = name_cstr.to_str() else {
+            return Err(FsError::InvalidUtf8 {
+                path: render(self.arena, self.paths, parent.path)?,
+            });
+        };
+
+        if name == "." || name == ".." {
+            return Ok(None);
+        }
+
+        let path = self.paths.join(parent.path, name)?;
+        let mut data: stat = unsafe { mem::zeroed() };
+
+        if unsafe { fstatat(dir, entry.d_name.as_ptr(), &mut data, AT_SYMLINK_NOFOLLOW) } < 0 {
+            let errno = errno();
+            // Removed between readdir and the stat, it is simply gone.
+            if errno == ENOENT {
+                return Ok(None);
+            }
+            return Err(FsError::os(errno, render(self.arena, self.paths, path)?));
+        }
+
+        let mut followed = false;
+
+        if self.options.follow_symlinks && file_type(data.st_mode) == FileType::SymLink {
+            let mut target: stat = unsafe { mem::zeroed() };
+
+            if unsafe { fstatat(dir, entry.d_name.as_ptr(), &mut target, 0) } == 0 {
+                data = target;
+                followed = true;
+            }
+        }
+
+        Ok(Some(WalkEntry::new(
+            path,
+            parent.depth + 1,
+            &data,
+            followed,
+        )))
+    }
+
+    fn visit(&mut self, entry: WalkEntry) {
+        let descend =
+            entry.is_dir() && (!self.options.same_filesystem || entry.device == self.device);
+
+        if !descend {
+            self.ready.push_back(Ok(entry));
+            return;
+        }
+
+        let ancestor = self
+            .stack
+            .iter()
+            .any(|frame| frame.entry.device == entry.device && frame.entry.inode == entry.inode);
+
+        if ancestor {
+            let error = match render(self.arena, self.paths, entry.path) {
+                Ok(path) => FsError::Loop { path },
+                Err(error) => FsError::Alloc(error),
+            };
+            self.ready.push_back(Err(error));
+            return;
+        }
+
+        match self.options.order {
+            WalkOrder::PreOrder => {
+                self.ready.push_back(Ok(entry));
+                self.pending = Some(entry);
+            }
+            WalkOrder::PostOrder => self.enter(entry),
+        }
+    }
+
+    // The names from `base` down to `entry`, each level of the walk adds one.
+    fn relative(&self, base: &WalkEntry, entry: &WalkEntry) -> Option<String> {
+        let mut names = Vec::new();
+        let mut current = Some(entry.path);
+
+        for _ in base.depth..entry.depth {
+            let id = current?;
+            names.push(self.paths.name(id).as_str());
+            current = self.paths.parent(id);
+        }
+
+        names.reverse();
+        Some(names.join("/"))
+    }
+
+    // A directory fd and the path of `entry` relative to it, good until the
+    // walker moves on. The closest open directory above the entry is used, so
+    // the path stays short however deep the entry is. Only the root, or an
+    // entry with no open ancestor left, gets its full path.
+    pub(super) fn locate(&self, entry: &WalkEntry) -> Result<(i32, String), AllocError> {
+        let base = self
+            .stack
+            .iter()
+            .rev()
+            .find(|frame| !frame.dirp.is_null() && frame.entry.depth < entry.depth);
+
+        match base.and_then(|frame| Some((frame, self.relative(&frame.entry, entry)?))) {
+            Some((frame, relative)) => Ok((unsafe { dirfd(frame.dirp) }, relative)),
+            None => Ok((AT_FDCWD, render(self.arena, self.paths, entry.path)?)),
+        }
+    }
+
+    fn open(&self, entry: &WalkEntry) -> Result<*mut DIR, FsError> {
+        let (dir, path) = self.locate(entry)?;
+
+        // A root given by its path may be a link itself.
+        let nofollow = if self.options.follow_symlinks || dir == AT_FDCWD {
+            0
+        } else {
+            O_NOFOLLOW
+        };
+
+        let scratch = self.arena.scratch();
+        let flags = O_RDONLY | O_DIRECTORY | O_CLOEXEC | nofollow;
+        let fd = unsafe { openat(dir, cstr(&scratch, &path)?, flags) };
+
+        let dirp = if fd < 0 {
+            core::ptr::null_mut()
+        } else {
+            unsafe { fdopendir(fd) }
+        };
+
+        if dirp.is_null() {
+            let errno = errno();
+
+            if fd >= 0 {
+                unsafe { close(fd) };
+            }
+
+            return Err(FsError::os(
+                errno,
+                render(self.arena, self.paths, entry.path)?,
+            ));
+        }
+
+        Ok(dirp)
+    }
+
+    fn enter(&mut self, entry: WalkEntry) {
+        let dirp = match self.open(&entry) {
+            Ok(dirp) => dirp,
+            Err(error) => {
+                self.ready.push_back(Err(error));
+
+                if self.options.order == WalkOrder::PostOrder {
+                    self.ready.push_back(Ok(entry));
+                }
+                return;
+            }
+        };
+
+        let open = self
+            .stack
+            .iter()
+            .filter(|frame| !frame.dirp.is_null())
+            .count();
+
+        // The parent was only still needed to open this directory.
+        if open >= self.options.max_open_dirs.max(1) {
+            self.close_top();
+        }
+
+        self.stack.push(Frame {
+            dirp,
+            entry,
+            skip: false,
+            buffered: None,
+        });
+    }
+
+    // Reads the rest of the top directory ahead and closes it.
+    fn close_top(&mut self) {
+        let Some(frame) = self.stack.last() else {
+            return;
+        };
+
+        if frame.dirp.is_null() {
+            return;
+        }
+
+        if frame.buffered.is_none() {
+            let mut buffered = VecDeque::new();
+            let dir = unsafe { dirfd(frame.dirp) };
+
+            // A skipped directory has nothing left to read.
+            let mut entry = if frame.skip {
+                core::ptr::null_mut()
+            } else {
+                unsafe { readdir(frame.dirp) }
+            };
+
+            while !entry.is_null() {
+                match self.child(&frame.entry, dir, unsafe { &*entry }) {
+                    Ok(Some(child)) => buffered.push_back(Ok(child)),
+                    Ok(None) => {}
+                    Err(error) => buffered.push_back(Err(error)),
+                }
+
+                entry = unsafe { readdir(frame.dirp) };
+            }
+
+            self.stack.last_mut().unwrap().buffered = Some(buffered);
+        }
+
+        let frame = self.stack.last_mut().unwrap();
+        unsafe { closedir(frame.dirp) };
+        frame.dirp = core::ptr::null_mut();
+    }
+
+    // Pops the top directory. A closed parent with subdirectories still to
+    // enter is reopened through "..", which keeps working however long the
+    // path is. It is only trusted if it leads back to the same directory,
+    // otherwise the children are opened from further up.
+    fn leave(&mut self) -> Option<WalkEntry> {
+        let frame = self.stack.pop()?;
+
+        if let Some(parent) = self.stack.last_mut() {
+            if parent.dirp.is_null() && parent.has_subdirectories() && !frame.dirp.is_null() {
+                let flags = O_RDONLY | O_DIRECTORY | O_CLOEXEC;
+                let fd = unsafe { openat(dirfd(frame.dirp), c"..".as_ptr(), flags) };
+
+                if fd >= 0 {
+                    let mut data: stat = unsafe { mem::zeroed() };
+                    let same = unsafe { fstat(fd, &mut data) } == 0
+                        && data.st_dev == parent.entry.device
+                        && data.st_ino == parent.entry.inode;
+
+                    if same {
+                        parent.dirp = unsafe { fdopendir(fd) };
+                    }
+
+                    if parent.dirp.is_null() {
+                        unsafe { close(fd) };
+                    }
+                }
+            }
+        }
+
+        if !frame.dirp.is_null() {
+            unsafe { closedir(frame.dirp) };
+        }
+
+        Some(frame.entry)
+    }
+}
+
+impl Iterator for Walker<'_> {
+    type Item = Result<WalkEntry, FsError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(root) = self.root.take() {
+            match self.root(root) {
+                Ok(entry) => self.visit(entry),
+                Err(error) => return Some(Err(error)),
+            }
+        }
+
+        loop {
+            if let Some(item) = self.ready.pop_front() {
+                return Some(item);
+            }
+
+            if let Some(entry) = self.pending.take() {
+                self.enter(entry);
+                continue;
+            }
+
+            let frame = self.stack.last_mut()?;
+            let item = match &mut frame.buffered {
+                _ if frame.skip => None,
+                Some(buffered) => buffered.pop_front().map(|item| item.map(Some)),
+                None => {
+                    let (dirp, parent) = (frame.dirp, frame.entry);
+                    let entry = unsafe { readdir(dirp) };
+
+                    (!entry.is_null())
+                        .then(|| self.child(&parent, unsafe { dirfd(dirp) }, unsafe { &*entry }))
+                }
+            };
+
+            match item {
+                Some(Ok(Some(child))) => self.visit(child),
+                Some(Ok(None)) => {}
+                Some(Err(error)) => return Some(Err(error)),
+                None => {
+                    let entry = self.leave()?;
+
+                    if self.options.order == WalkOrder::PostOrder {
+                        return Some(Ok(entry));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Walker<'_> {
+    fn drop(&mut self) {
+        for frame in self.stack.drain(..) {
+            if !frame.dirp.is_null() {
+                unsafe { closedir(frame.dirp) };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::unix::filesystem::{Filesystem, INodeKind, ScanOptions};
+    use alloc::string::String;
+
+    fn temp_tree(name: &str) -> String {
+        let root =
+            std::env::temp_dir().join(alloc::format!("monolith-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(root.join("a/b/c")).unwrap();
+        std::fs::write(root.join("a/b/c/file"), "").unwrap();
+        std::fs::write(root.join("a/x.txt"), "").unwrap();
+        std::os::unix::fs::symlink("../..", root.join("a/b/up")).unwrap();
+        std::os::unix::fs::symlink("a", root.join("link")).unwrap();
+        String::from(root.to_str().unwrap())
+    }
+
+    // std::fs::remove_dir_all keeps a descriptor open for every level it is
+    // in, which the deep trees below would run out of.
+    fn remove_tree(root: &std::path::Path) {
+        let status = std::process::Command::new("rm")
+            .arg("-rf")
+            .arg(root)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    fn names(filesystem: &Filesystem, options: WalkOptions) -> Vec<&str> {
+        filesystem
+            .walk(options)
+            .map(|entry| entry.unwrap().name(filesystem.paths()).as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_walker_order() {
+        let root = temp_tree("walk-order");
+        let filesystem = Filesystem::new(&root).unwrap();
+
+        let pre = names(
+            &filesystem,
+            WalkOptions {
+                same_filesystem: true,
+                ..Default::default()
+            },
+        );
+        let position = |names: &[&str], name| names.iter().position(|n| *n == name).unwrap();
+
+        assert_eq!(pre.len(), 8);
+        assert_eq!(position(&pre, "c") + 1, position(&pre, "file"));
+        assert!(position(&pre, "b") < position(&pre, "c"));
+        assert!(position(&pre, "a") < position(&pre, "x.txt"));
+        assert!(pre[0].starts_with("monolith-walk-order"));
+
+        let post = names(
+            &filesystem,
+            WalkOptions {
+                order: WalkOrder::PostOrder,
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(post.len(), 8);
+        assert_eq!(position(&post, "file") + 1, position(&post, "c"));
+        assert!(position(&post, "c") < position(&post, "b"));
+        assert!(position(&post, "x.txt") < position(&post, "a"));
+        assert_eq!(post.last(), pre.first());
+
+        let link = filesystem
+            .walk(WalkOptions::default())
+            .map(Result::unwrap)
+            .find(|entry| entry.name(filesystem.paths()) == "link")
+            .unwrap();
+        assert_eq!(link.file_type, FileType::SymLink);
+        assert!(!link.followed);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_walker_follow_symlinks() {
+        let root = temp_tree("walk-follow");
+        let filesystem = Filesystem::new(&root).unwrap();
+        let arena = Arena::new(4096);
+
+        let (entries, errors): (Vec<_>, Vec<_>) = filesystem
+            .walk(WalkOptions {
+                follow_symlinks: true,
+                ..Default::default()
+            })
+            .partition(Result::is_ok);
+
+        let entries: Vec<_> = entries.into_iter().map(Result::unwrap).collect();
+        assert_eq!(entries.len(), 11);
+
+        let link = entries
+            .iter()
+            .find(|entry| entry.name(filesystem.paths()) == "link")
+            .unwrap();
+        assert!(link.followed && link.is_dir());
+
+        let expected = alloc::format!("{}/link/b/c/file", root);
+        assert!(entries.iter().any(|entry| {
+            filesystem.paths().render(&arena, entry.path).unwrap() == expected.as_str()
+        }));
+
+        assert_eq!(errors.len(), 2);
+        for error in errors {
+            assert!(matches!(error, Err(FsError::Loop { .. })));
+        }
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_walker_skip_subtree() {
+        let root = temp_tree("walk-skip");
+        let filesystem = Filesystem::new(&root).unwrap();
+        let mut walker = filesystem.walk(WalkOptions::default());
+        let mut seen = Vec::new();
+
+        while let Some(entry) = walker.next() {
+            let name = entry.unwrap().name(filesystem.paths()).as_str();
+            seen.push(name);
+
+            if name == "b" {
+                walker.skip_subtree();
+            }
+
+            // Files have no subtree, this must not cut their directory short.
+            if name == "x.txt" || name == "link" {
+                walker.skip_subtree();
+            }
+        }
+
+        assert_eq!(seen.len(), 5);
+        assert!(!seen.contains(&"c"));
+        assert!(seen.contains(&"x.txt"));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_walker_skip_siblings() {
+        let root = temp_tree("walk-siblings");
+        let filesystem = Filesystem::new(&root).unwrap();
+        let mut walker = filesystem.walk(WalkOptions::default());
+        let mut seen = Vec::new();
+
+        while let Some(entry) = walker.next() {
+            let entry = entry.unwrap();
+            seen.push(entry.name(filesystem.paths()).as_str());
+
+            if entry.depth == 2 {
+                walker.skip_siblings();
+            }
+        }
+
+        // One entry of "a" is walked, the top level is not cut short.
+        let position = |name| seen.iter().position(|seen| *seen == name);
+        assert_eq!(
+            position("b").is_some() as usize + position("x.txt").is_some() as usize,
+            1
+        );
+        assert!(position("link").is_some());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_walker_deep_tree() {
+        let root =
+            std::env::temp_dir().join(alloc::format!("monolith-walk-deep-{}", std::process::id()));
+        let deep = (0..1000).fold(root.clone(), |path, _| path.join("d"));
+        std::fs::create_dir_all(&deep).unwrap();
+        std::fs::write(deep.join("leaf"), "").unwrap();
+
+        let filesystem = Filesystem::new(root.to_str().unwrap()).unwrap();
+        let deepest = filesystem
+            .walk(WalkOptions::default())
+            .map(|entry| entry.unwrap().depth)
+            .max();
+        assert_eq!(deepest, Some(1001));
+
+        let tree = filesystem.read(&ScanOptions::default()).unwrap();
+        assert_eq!(tree.depth_first().count(), 1002);
+        drop(tree);
+
+        remove_tree(&root);
+    }
+
+    #[test]
+    fn test_walker_long_paths() {
+        use std::os::fd::{AsRawFd, FromRawFd};
+
+        // Far past PATH_MAX, with a file next to every directory so that the
+        // directories closed to stay under the cap have entries left to read.
+        let root =
+            std::env::temp_dir().join(alloc::format!("monolith-walk-long-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+
+        let name = std::ffi::CString::new("n".repeat(200)).unwrap();
+        let mut dir = std::fs::File::open(&root).unwrap();
+
+        for _ in 0..100 {
+            unsafe {
+                let file = libc::openat(
+                    dir.as_raw_fd(),
+                    c"file".as_ptr(),
+                    libc::O_CREAT | libc::O_WRONLY | libc::O_CLOEXEC,
+                    0o644,
+                );
+                assert!(file >= 0);
+                libc::close(file);
+
+                assert_eq!(libc::mkdirat(dir.as_raw_fd(), name.as_ptr(), 0o755), 0);
+                let next = libc::openat(
+                    dir.as_raw_fd(),
+                    name.as_ptr(),
+                    O_RDONLY | O_DIRECTORY | O_CLOEXEC,
+                );
+                assert!(next >= 0);
+                dir = std::fs::File::from_raw_fd(next);
+            }
+        }
+
+        // An ignore file and a link at the bottom, both far past PATH_MAX.
+        unsafe {
+            for name in [c"file", c".gitignore"] {
+                let file = libc::openat(
+                    dir.as_raw_fd(),
+                    name.as_ptr(),
+                    libc::O_CREAT | libc::O_WRONLY | libc::O_CLOEXEC,
+                    0o644,
+                );
+                assert!(file >= 0);
+                libc::close(file);
+            }
+
+            let file = libc::openat(
+                dir.as_raw_fd(),
+                c".gitignore".as_ptr(),
+                libc::O_WRONLY | libc::O_CLOEXEC,
+            );
+            assert!(file >= 0);
+            assert_eq!(libc::write(file, c"file\n".as_ptr() as *const _, 5), 5);
+            libc::close(file);
+
+            assert_eq!(
+                libc::symlinkat(c"file".as_ptr(), dir.as_raw_fd(), c"link".as_ptr()),
+                0
+            );
+        }
+
+        let filesystem = Filesystem::new(root.to_str().unwrap()).unwrap();
+
+        for order in [WalkOrder::PreOrder, WalkOrder::PostOrder] {
+            let entries: Vec<_> = filesystem
+                .walk(WalkOptions {
+                    order,
+                    max_open_dirs: 2,
+                    ..Default::default()
+                })
+                .map(Result::unwrap)
+                .collect();
+
+            assert_eq!(entries.len(), 204);
+            assert_eq!(entries.iter().filter(|entry| entry.is_dir()).count(), 101);
+            assert_eq!(entries.iter().map(|entry| entry.depth).max(), Some(101));
+        }
+
+        let tree = filesystem
+            .read(&ScanOptions {
+                ignore_files: true,
+                ..Default::default()
+            })
+            .unwrap();
+        let paths = filesystem.paths();
+
+        // The file at the bottom is ignored, the ones above it are not.
+        let files = tree
+            .depth_first()
+            .filter(|node| matches!(node.kind, INodeKind::File))
+            .filter(|node| node.name(paths).as_str() == "file")
+            .count();
+        assert_eq!(files, 100);
+        assert!(tree
+            .depth_first()
+            .any(|node| node.name(paths).as_str() == ".gitignore"));
+
+        let link = tree
+            .depth_first()
+            .find(|node| node.name(paths).as_str() == "link")
+            .unwrap();
+        match link.kind {
+            INodeKind::Symlink(target) => assert_eq!(paths.name(target).as_str(), "file"),
+            _ => panic!("expected a symlink"),
+        }
+        drop(tree);
+
+        remove_tree(&root);
+    }
+}